@@ -0,0 +1,282 @@
+use std::io;
+
+const CPU_CLOCK_RATE: u32 = 20000000;
+
+// The VSU produces a stereo sample every 480 CPU cycles, 41.7kHz from the 20MHz clock
+pub const CYCLES_PER_SAMPLE: usize = 480;
+pub const NATIVE_SAMPLE_RATE: u32 = CPU_CLOCK_RATE / CYCLES_PER_SAMPLE as u32;
+
+// Enough buffered audio for a quarter of a second at the native rate
+const DEFAULT_BUFFER_FRAMES: usize = 10240;
+
+// Feedback for the DC blocking filter, putting its cutoff at around 30Hz
+const DC_BLOCKER_POLE: f64 = 0.995;
+
+// A fixed size queue of stereo frames, once full the oldest frames are dropped to make room for new ones
+pub struct RingBuffer {
+    frames: Vec<[i16; 2]>,
+    read: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        RingBuffer {
+            frames: vec![[0; 2]; capacity],
+            read: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.read = 0;
+        self.len = 0;
+    }
+
+    pub fn push(&mut self, frame: [i16; 2]) {
+        let capacity = self.frames.len();
+        if self.len == capacity {
+            self.read = (self.read + 1) % capacity;
+            self.len -= 1;
+        }
+
+        self.frames[(self.read + self.len) % capacity] = frame;
+        self.len += 1;
+    }
+
+    // Moves as many whole frames as fit into out as interleaved left/right samples, returning the number of
+    // samples written
+    pub fn drain_into(&mut self, out: &mut [i16]) -> usize {
+        let count = self.len.min(out.len() / 2);
+        for samples in out.chunks_exact_mut(2).take(count) {
+            samples.copy_from_slice(&self.frames[self.read]);
+            self.read = (self.read + 1) % self.frames.len();
+        }
+        self.len -= count;
+        count * 2
+    }
+}
+
+// Linearly interpolates the native rate stream to the requested output rate
+pub struct Resampler {
+    output_rate: u32,
+    step: f64,
+    position: f64,
+    previous: [i16; 2],
+}
+
+impl Resampler {
+    pub fn new(output_rate: u32) -> Self {
+        Resampler {
+            output_rate,
+            step: resample_step(output_rate),
+            position: 0.0,
+            previous: [0; 2],
+        }
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    pub fn set_output_rate(&mut self, output_rate: u32) {
+        self.output_rate = output_rate;
+        self.step = resample_step(output_rate);
+        self.position = 0.0;
+    }
+
+    pub fn push(&mut self, frame: [i16; 2], out: &mut RingBuffer) {
+        // position is where the next output frame falls between the previous input frame and this one
+        while self.position < 1.0 {
+            let t = self.position;
+            out.push([lerp(self.previous[0], frame[0], t), lerp(self.previous[1], frame[1], t)]);
+            self.position += self.step;
+        }
+        self.position -= 1.0;
+        self.previous = frame;
+    }
+}
+
+// The VSU only ever outputs positive levels, this takes out the resulting DC offset in the same way as the
+// capacitor on the real audio output so the stream is centred on zero
+struct DcBlocker {
+    previous_input: [f64; 2],
+    previous_output: [f64; 2],
+}
+
+impl DcBlocker {
+    fn new() -> Self {
+        DcBlocker {
+            previous_input: [0.0; 2],
+            previous_output: [0.0; 2],
+        }
+    }
+
+    fn filter(&mut self, frame: [i16; 2]) -> [i16; 2] {
+        let mut out = [0; 2];
+        for channel in 0..2 {
+            let input = frame[channel] as f64;
+            let output = input - self.previous_input[channel] + DC_BLOCKER_POLE * self.previous_output[channel];
+            self.previous_input[channel] = input;
+            self.previous_output[channel] = output;
+            out[channel] = output.round() as i16;
+        }
+        out
+    }
+}
+
+pub struct AudioOutput {
+    dc_blocker: DcBlocker,
+    resampler: Resampler,
+    buffer: RingBuffer,
+}
+
+impl AudioOutput {
+    pub fn new() -> Self {
+        AudioOutput {
+            dc_blocker: DcBlocker::new(),
+            resampler: Resampler::new(NATIVE_SAMPLE_RATE),
+            buffer: RingBuffer::new(DEFAULT_BUFFER_FRAMES),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.resampler.output_rate()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) -> io::Result<()> {
+        if sample_rate == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid sample rate"));
+        }
+
+        self.resampler.set_output_rate(sample_rate);
+        self.buffer.clear();
+        Ok(())
+    }
+
+    pub fn push(&mut self, frame: [i16; 2]) {
+        let frame = self.dc_blocker.filter(frame);
+        self.resampler.push(frame, &mut self.buffer);
+    }
+
+    pub fn samples_available(&self) -> usize {
+        self.buffer.len() * 2
+    }
+
+    pub fn read_samples(&mut self, out: &mut [i16]) -> usize {
+        self.buffer.drain_into(out)
+    }
+}
+
+fn resample_step(output_rate: u32) -> f64 {
+    CPU_CLOCK_RATE as f64 / (CYCLES_PER_SAMPLE as f64 * output_rate as f64)
+}
+
+fn lerp(a: i16, b: i16, t: f64) -> i16 {
+    (a as f64 + (b as f64 - a as f64) * t).round() as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(buffer: &mut RingBuffer) -> Vec<i16> {
+        let mut out = vec![0; buffer.len() * 2];
+        let count = buffer.drain_into(&mut out);
+        out.truncate(count);
+        out
+    }
+
+    #[test]
+    fn ring_buffer_drops_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        for i in 0..5 {
+            buffer.push([i, -i]);
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(drain(&mut buffer), vec![2, -2, 3, -3, 4, -4]);
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn ring_buffer_drains_whole_frames() {
+        let mut buffer = RingBuffer::new(4);
+        buffer.push([1, 2]);
+        buffer.push([3, 4]);
+
+        let mut out = [0; 3];
+        assert_eq!(buffer.drain_into(&mut out), 2);
+        assert_eq!(out, [1, 2, 0]);
+        assert_eq!(buffer.len(), 1);
+
+        // Wraps round the end of the storage
+        for i in 0..3 {
+            buffer.push([i, i]);
+        }
+        assert_eq!(drain(&mut buffer), vec![3, 4, 0, 0, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn resampler_native_rate() {
+        let mut resampler = Resampler::new(NATIVE_SAMPLE_RATE);
+        let mut buffer = RingBuffer::new(16);
+        resampler.push([100, -100], &mut buffer);
+        resampler.push([300, -300], &mut buffer);
+        resampler.push([500, -500], &mut buffer);
+
+        // Output runs one input frame behind
+        assert_eq!(drain(&mut buffer), vec![0, 0, 100, -100, 300, -300]);
+    }
+
+    #[test]
+    fn resampler_interpolates() {
+        let mut resampler = Resampler::new(NATIVE_SAMPLE_RATE * 2);
+        let mut buffer = RingBuffer::new(16);
+        resampler.push([100, 0], &mut buffer);
+        resampler.push([300, 0], &mut buffer);
+        assert_eq!(drain(&mut buffer), vec![0, 0, 50, 0, 100, 0, 200, 0]);
+
+        resampler.set_output_rate(NATIVE_SAMPLE_RATE / 2);
+        for i in 0..4 {
+            resampler.push([i * 100, 0], &mut buffer);
+        }
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn zero_sample_rate_rejected() {
+        let mut audio = AudioOutput::new();
+        assert!(audio.set_sample_rate(0).is_err());
+        assert_eq!(audio.sample_rate(), NATIVE_SAMPLE_RATE);
+        assert!(audio.set_sample_rate(48000).is_ok());
+        assert_eq!(audio.sample_rate(), 48000);
+    }
+
+    #[test]
+    fn dc_blocker_centres_output() {
+        let mut dc_blocker = DcBlocker::new();
+
+        // A square wave between 0 and 20000 ends up swinging either side of zero
+        let mut sum = 0i64;
+        let mut last = [0; 2];
+        for i in 0..20000 {
+            let level = if (i / 50) % 2 == 0 { 20000 } else { 0 };
+            last = dc_blocker.filter([level, level]);
+            if i >= 10000 {
+                sum += last[0] as i64;
+            }
+        }
+        assert!((sum / 10000).abs() < 200);
+        assert!(last[0] < 0);
+
+        // And silence settles back to zero
+        for _ in 0..20000 {
+            last = dc_blocker.filter([0, 0]);
+        }
+        assert_eq!(last, [0, 0]);
+    }
+}
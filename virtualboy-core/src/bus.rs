@@ -0,0 +1,56 @@
+// A device mapped into the CPU's address space. Addresses are offsets into the device's region and are already
+// aligned to the size of the access.
+pub trait BusDevice {
+    fn read_byte(&mut self, addr: u32) -> u8;
+    fn read_halfword(&mut self, addr: u32) -> u16;
+    fn write_byte(&mut self, addr: u32, val: u8);
+    fn write_halfword(&mut self, addr: u32, val: u16);
+
+    // Reads a byte without any of the side effects a real read might have, for the debugger
+    fn peek(&self, addr: u32) -> u8;
+
+    // The data bus is 16 bits wide, so by default a word access is two halfword accesses
+    fn read_word(&mut self, addr: u32) -> u32 {
+        (self.read_halfword(addr) as u32) | ((self.read_halfword(addr + 2) as u32) << 16)
+    }
+
+    fn write_word(&mut self, addr: u32, val: u32) {
+        self.write_halfword(addr, val as u16);
+        self.write_halfword(addr + 2, (val >> 16) as u16);
+    }
+}
+
+// A region with nothing connected to it, reads return 0 and writes are dropped
+pub struct Unmapped {
+    name: &'static str,
+}
+
+impl Unmapped {
+    pub fn new(name: &'static str) -> Self {
+        Unmapped { name }
+    }
+}
+
+impl BusDevice for Unmapped {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        println!("WARN: Reading from {} [0x{:08x}]", self.name, addr);
+        0
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        println!("WARN: Reading from {} [0x{:08x}]", self.name, addr);
+        0
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        println!("WARN: Writing to {} [0x{:08x}] = 0x{:02x}", self.name, addr, val);
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        println!("WARN: Writing to {} [0x{:08x}] = 0x{:04x}", self.name, addr, val);
+    }
+
+    fn peek(&self, _addr: u32) -> u8 {
+        0
+    }
+}
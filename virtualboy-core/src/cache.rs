@@ -0,0 +1,237 @@
+use std::fmt::{Display, Formatter, Result};
+
+use super::interconnect::Interconnect;
+
+pub const NUM_CACHE_ENTRIES: usize = 128;
+
+#[derive(Default, Clone, Copy)]
+pub struct CacheEntry {
+    tag: u32,
+    valid: [bool; 2],
+    data: [u32; 2],
+}
+
+impl CacheEntry {
+    fn tag_word(&self) -> u32 {
+        let mut val = self.tag & 0x003fffff;
+        val |= if self.valid[0] { 1 << 22 } else { 0 };
+        val |= if self.valid[1] { 1 << 23 } else { 0 };
+        val
+    }
+
+    fn set_tag_word(&mut self, val: u32) {
+        self.tag = val & 0x003fffff;
+        self.valid[0] = ((val >> 22) & 0x01) != 0;
+        self.valid[1] = ((val >> 23) & 0x01) != 0;
+    }
+}
+
+impl Display for CacheEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "tag: 0x{:06x} [{}] 0x{:08x} [{}] 0x{:08x}",
+            self.tag,
+            if self.valid[0] { "v" } else { " " },
+            self.data[0],
+            if self.valid[1] { "v" } else { " " },
+            self.data[1])
+    }
+}
+
+// The V810's 1KiB direct mapped instruction cache. Each of the 128 entries holds an 8 byte block made up of
+// two independently valid 4 byte sub-blocks.
+pub struct InstructionCache {
+    enabled: bool,
+    entries: [CacheEntry; NUM_CACHE_ENTRIES],
+
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for InstructionCache {
+    fn default() -> Self {
+        InstructionCache::new()
+    }
+}
+
+impl InstructionCache {
+    pub fn new() -> Self {
+        InstructionCache {
+            enabled: false,
+            entries: [CacheEntry::default(); NUM_CACHE_ENTRIES],
+
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+
+    pub fn entry(&self, index: usize) -> CacheEntry {
+        self.entries[index]
+    }
+
+    // Fetches the halfword at addr, returning it along with whether the fetch hit the cache
+    pub fn read_halfword(&mut self, interconnect: &mut Interconnect, addr: u32) -> (u16, bool) {
+        if !self.enabled {
+            return (interconnect.read_halfword(addr), false);
+        }
+
+        let index = ((addr >> 3) & 0x7f) as usize;
+        let sub_block = ((addr >> 2) & 0x01) as usize;
+        let tag = addr >> 10;
+
+        let entry = &mut self.entries[index];
+        let hit = entry.tag == tag && entry.valid[sub_block];
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            if entry.tag != tag {
+                entry.tag = tag;
+                entry.valid = [false, false];
+            }
+            entry.data[sub_block] = interconnect.read_word(addr & 0xfffffffc);
+            entry.valid[sub_block] = true;
+        }
+
+        let word = entry.data[sub_block];
+        let halfword = if (addr & 0x02) != 0 { (word >> 16) as u16 } else { word as u16 };
+        (halfword, hit)
+    }
+
+    pub fn clear(&mut self, start: usize, count: usize) {
+        for entry in self.entries.iter_mut().skip(start).take(count) {
+            entry.valid = [false, false];
+        }
+    }
+
+    pub fn dump(&self, interconnect: &mut Interconnect, addr: u32) {
+        for (i, entry) in self.entries.iter().enumerate() {
+            let entry_addr = addr.wrapping_add(i as u32 * 8);
+            interconnect.write_word(entry_addr, entry.data[0]);
+            interconnect.write_word(entry_addr.wrapping_add(4), entry.data[1]);
+        }
+        for (i, entry) in self.entries.iter().enumerate() {
+            let tag_addr = addr.wrapping_add(0x400 + i as u32 * 4);
+            interconnect.write_word(tag_addr, entry.tag_word());
+        }
+    }
+
+    pub fn restore(&mut self, interconnect: &mut Interconnect, addr: u32) {
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            let entry_addr = addr.wrapping_add(i as u32 * 8);
+            entry.data[0] = interconnect.read_word(entry_addr);
+            entry.data[1] = interconnect.read_word(entry_addr.wrapping_add(4));
+        }
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            let tag_addr = addr.wrapping_add(0x400 + i as u32 * 4);
+            entry.set_tag_word(interconnect.read_word(tag_addr));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rom::{Rom, MIN_ROM_SIZE};
+
+    const CODE_ADDR: u32 = 0x05000000;
+    const DUMP_ADDR: u32 = 0x05008000;
+
+    fn interconnect() -> Interconnect {
+        let mut interconnect = Interconnect::new(Rom::from_bytes(&[0; MIN_ROM_SIZE]).unwrap());
+        for i in 0..0x400 {
+            interconnect.write_halfword(CODE_ADDR + i * 2, i as u16);
+        }
+        interconnect
+    }
+
+    fn enabled_cache() -> InstructionCache {
+        let mut cache = InstructionCache::new();
+        cache.set_enabled(true);
+        cache
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let mut interconnect = interconnect();
+        let mut cache = enabled_cache();
+
+        // A miss fills the 4 byte sub-block, so the other halfword in it hits
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR), (0x0000, false));
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR + 2), (0x0001, true));
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR + 4), (0x0002, false));
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR), (0x0000, true));
+        assert_eq!(cache.stats(), (2, 2));
+
+        // The same entry for a different tag replaces it
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR + 0x400), (0x0200, false));
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR), (0x0000, false));
+        assert_eq!(cache.stats(), (2, 4));
+
+        // Stale contents are returned until the entry is cleared
+        interconnect.write_halfword(CODE_ADDR, 0xbeef);
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR), (0x0000, true));
+
+        // Disabled, every fetch goes to memory and isn't counted
+        cache.set_enabled(false);
+        assert_eq!(cache.read_halfword(&mut interconnect, CODE_ADDR), (0xbeef, false));
+        assert_eq!(cache.stats(), (3, 4));
+    }
+
+    #[test]
+    fn clear_range() {
+        let mut interconnect = interconnect();
+        let mut cache = enabled_cache();
+        for entry in 0..NUM_CACHE_ENTRIES as u32 {
+            cache.read_halfword(&mut interconnect, CODE_ADDR + entry * 8);
+        }
+
+        cache.clear(4, 3);
+
+        for entry in 0..NUM_CACHE_ENTRIES as u32 {
+            let (_, hit) = cache.read_halfword(&mut interconnect, CODE_ADDR + entry * 8);
+            assert_eq!(hit, !(4..7).contains(&entry), "entry {}", entry);
+        }
+
+        // Clearing past the last entry stops there
+        cache.clear(126, 10);
+        assert!(!cache.read_halfword(&mut interconnect, CODE_ADDR + 126 * 8).1);
+        assert!(cache.read_halfword(&mut interconnect, CODE_ADDR + 125 * 8).1);
+    }
+
+    #[test]
+    fn dump_and_restore() {
+        let mut interconnect = interconnect();
+        let mut cache = enabled_cache();
+        cache.read_halfword(&mut interconnect, CODE_ADDR + 0x10);
+        cache.read_halfword(&mut interconnect, CODE_ADDR + 0x16);
+
+        cache.dump(&mut interconnect, DUMP_ADDR);
+        // Entry 2 holds both sub-blocks, with the tag and valid bits in the tag area
+        assert_eq!(interconnect.read_word(DUMP_ADDR + 0x10), 0x00090008);
+        assert_eq!(interconnect.read_word(DUMP_ADDR + 0x14), 0x000b000a);
+        assert_eq!(interconnect.read_word(DUMP_ADDR + 0x408), 0x00c00000 | (CODE_ADDR >> 10));
+        assert_eq!(interconnect.read_word(DUMP_ADDR + 0x40c), 0x00000000);
+
+        let mut restored = enabled_cache();
+        restored.restore(&mut interconnect, DUMP_ADDR);
+        for i in 0..NUM_CACHE_ENTRIES {
+            assert_eq!(restored.entry(i).to_string(), cache.entry(i).to_string(), "entry {}", i);
+        }
+
+        // The restored entries hit without going back to memory
+        interconnect.write_halfword(CODE_ADDR + 0x16, 0xbeef);
+        assert_eq!(restored.read_halfword(&mut interconnect, CODE_ADDR + 0x16), (0x000b, true));
+    }
+}
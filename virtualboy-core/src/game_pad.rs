@@ -0,0 +1,254 @@
+// Bits of the serial data registers SDLR/SDHR, as shifted out by the controller
+const SDR_LOW_BATTERY: u16 = 1 << 0;
+const SDR_SIGNATURE: u16 = 1 << 1;
+const SDR_A: u16 = 1 << 2;
+const SDR_B: u16 = 1 << 3;
+const SDR_R: u16 = 1 << 4;
+const SDR_L: u16 = 1 << 5;
+const SDR_RIGHT_UP: u16 = 1 << 6;
+const SDR_RIGHT_RIGHT: u16 = 1 << 7;
+const SDR_LEFT_RIGHT: u16 = 1 << 8;
+const SDR_LEFT_LEFT: u16 = 1 << 9;
+const SDR_LEFT_DOWN: u16 = 1 << 10;
+const SDR_LEFT_UP: u16 = 1 << 11;
+const SDR_START: u16 = 1 << 12;
+const SDR_SELECT: u16 = 1 << 13;
+const SDR_RIGHT_LEFT: u16 = 1 << 14;
+const SDR_RIGHT_DOWN: u16 = 1 << 15;
+const SDR_BUTTONS: u16 = 0xfffc;
+
+const SCR_ABORT: u8 = 1 << 0;
+const SCR_SI_STAT: u8 = 1 << 1;
+const SCR_HW_SI: u8 = 1 << 2;
+const SCR_SOFT_CK: u8 = 1 << 4;
+const SCR_PARA_SI: u8 = 1 << 5;
+const SCR_K_INT_INH: u8 = 1 << 7;
+// Bits 2, 3 and 6 of SCR always read back as set
+const SCR_READ_SET: u8 = 0x4c;
+
+// Clocking the 16 bits out of the controller during a hardware read takes roughly 32us
+const HARDWARE_READ_CYCLES: usize = 640;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Buttons {
+    pub a: bool,
+    pub b: bool,
+    pub l: bool,
+    pub r: bool,
+    pub start: bool,
+    pub select: bool,
+    pub left_up: bool,
+    pub left_down: bool,
+    pub left_left: bool,
+    pub left_right: bool,
+    pub right_up: bool,
+    pub right_down: bool,
+    pub right_left: bool,
+    pub right_right: bool,
+}
+
+impl Buttons {
+    fn sdr_bits(&self) -> u16 {
+        let buttons = [
+            (self.a, SDR_A),
+            (self.b, SDR_B),
+            (self.r, SDR_R),
+            (self.l, SDR_L),
+            (self.right_up, SDR_RIGHT_UP),
+            (self.right_right, SDR_RIGHT_RIGHT),
+            (self.left_right, SDR_LEFT_RIGHT),
+            (self.left_left, SDR_LEFT_LEFT),
+            (self.left_down, SDR_LEFT_DOWN),
+            (self.left_up, SDR_LEFT_UP),
+            (self.start, SDR_START),
+            (self.select, SDR_SELECT),
+            (self.right_left, SDR_RIGHT_LEFT),
+            (self.right_down, SDR_RIGHT_DOWN),
+        ];
+        buttons.iter().filter(|(pressed, _)| *pressed).fold(0, |val, (_, bit)| val | bit)
+    }
+}
+
+pub struct GamePad {
+    buttons: Buttons,
+    low_battery: bool,
+
+    reg_sdr: u16,
+    reg_k_int_inh: bool,
+    soft_clock: bool,
+
+    hardware_read_cycles_left: Option<usize>,
+    // Controller state latched for a software read, and the number of bits clocked out of it so far
+    latched: u16,
+    bits_read: u32,
+}
+
+impl GamePad {
+    pub fn new() -> Self {
+        GamePad {
+            buttons: Buttons::default(),
+            low_battery: false,
+
+            reg_sdr: 0,
+            reg_k_int_inh: false,
+            soft_clock: false,
+
+            hardware_read_cycles_left: None,
+            latched: 0,
+            bits_read: 0,
+        }
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.buttons = buttons;
+    }
+
+    pub fn set_low_battery(&mut self, low_battery: bool) {
+        self.low_battery = low_battery;
+    }
+
+    // Advances any hardware read in progress, returning whether the key interrupt is being requested
+    pub fn cycles(&mut self, cycles: usize) -> bool {
+        if let Some(cycles_left) = self.hardware_read_cycles_left {
+            if cycles_left <= cycles {
+                self.hardware_read_cycles_left = None;
+                self.reg_sdr = self.controller_data();
+            } else {
+                self.hardware_read_cycles_left = Some(cycles_left - cycles);
+            }
+        }
+
+        !self.reg_k_int_inh && self.hardware_read_cycles_left.is_none() && (self.reg_sdr & SDR_BUTTONS) != 0
+    }
+
+    pub fn read_sdlr(&self) -> u8 {
+        self.reg_sdr as u8
+    }
+
+    pub fn read_sdhr(&self) -> u8 {
+        (self.reg_sdr >> 8) as u8
+    }
+
+    pub fn read_scr(&self) -> u8 {
+        let mut val = SCR_READ_SET;
+        val |= if self.hardware_read_cycles_left.is_some() { SCR_SI_STAT } else { 0 };
+        val |= if self.soft_clock { SCR_SOFT_CK } else { 0 };
+        val |= if self.reg_k_int_inh { SCR_K_INT_INH } else { 0 };
+        val
+    }
+
+    pub fn write_scr(&mut self, val: u8) {
+        self.reg_k_int_inh = (val & SCR_K_INT_INH) != 0;
+
+        if (val & SCR_ABORT) != 0 {
+            self.hardware_read_cycles_left = None;
+        }
+
+        if (val & SCR_HW_SI) != 0 && self.hardware_read_cycles_left.is_none() {
+            self.hardware_read_cycles_left = Some(HARDWARE_READ_CYCLES);
+        }
+
+        if (val & SCR_PARA_SI) != 0 {
+            self.latched = self.controller_data();
+            self.bits_read = 0;
+        }
+
+        // Each rising edge of the software clock shifts the next bit of the latched state into SDR, most
+        // significant bit first
+        let soft_clock = (val & SCR_SOFT_CK) != 0;
+        if soft_clock && !self.soft_clock && self.bits_read < 16 {
+            let bit = (self.latched >> (15 - self.bits_read)) & 0x01;
+            self.reg_sdr = (self.reg_sdr << 1) | bit;
+            self.bits_read += 1;
+        }
+        self.soft_clock = soft_clock;
+    }
+
+    fn controller_data(&self) -> u16 {
+        let mut val = SDR_SIGNATURE | self.buttons.sdr_bits();
+        val |= if self.low_battery { SDR_LOW_BATTERY } else { 0 };
+        val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hardware_read() {
+        let mut game_pad = GamePad::new();
+        game_pad.set_buttons(Buttons { a: true, start: true, ..Buttons::default() });
+        game_pad.set_low_battery(true);
+
+        game_pad.write_scr(SCR_HW_SI);
+        assert_eq!(game_pad.read_scr(), SCR_READ_SET | SCR_SI_STAT);
+        assert!(!game_pad.cycles(HARDWARE_READ_CYCLES - 1));
+        assert_eq!(game_pad.read_sdlr(), 0x00);
+
+        assert!(game_pad.cycles(1));
+        assert_eq!(game_pad.read_scr(), SCR_READ_SET);
+        assert_eq!(game_pad.read_sdlr(), 0x07);
+        assert_eq!(game_pad.read_sdhr(), 0x10);
+    }
+
+    #[test]
+    fn software_read() {
+        let mut game_pad = GamePad::new();
+        game_pad.set_buttons(Buttons { left_up: true, ..Buttons::default() });
+
+        game_pad.write_scr(SCR_PARA_SI);
+        // Bits are clocked in most significant first, only on the clock's rising edge
+        for _ in 0..5 {
+            game_pad.write_scr(SCR_SOFT_CK);
+            game_pad.write_scr(SCR_SOFT_CK);
+            game_pad.write_scr(0);
+        }
+        assert_eq!(game_pad.read_sdlr(), 0x01);
+        assert_eq!(game_pad.read_sdhr(), 0x00);
+
+        // Clocking past the 16th bit shifts in nothing more
+        for _ in 0..16 {
+            game_pad.write_scr(SCR_SOFT_CK);
+            game_pad.write_scr(0);
+        }
+        assert_eq!(game_pad.read_sdlr(), 0x02);
+        assert_eq!(game_pad.read_sdhr(), 0x08);
+
+        game_pad.write_scr(SCR_SOFT_CK);
+        assert_eq!(game_pad.read_scr(), SCR_READ_SET | SCR_SOFT_CK);
+    }
+
+    #[test]
+    fn abort_hardware_read() {
+        let mut game_pad = GamePad::new();
+        game_pad.set_buttons(Buttons { b: true, ..Buttons::default() });
+
+        game_pad.write_scr(SCR_HW_SI);
+        game_pad.cycles(HARDWARE_READ_CYCLES / 2);
+        game_pad.write_scr(SCR_ABORT);
+        assert_eq!(game_pad.read_scr(), SCR_READ_SET);
+
+        assert!(!game_pad.cycles(HARDWARE_READ_CYCLES));
+        assert_eq!(game_pad.read_sdlr(), 0x00);
+    }
+
+    #[test]
+    fn key_interrupt() {
+        let mut game_pad = GamePad::new();
+
+        // Only the signature bit set isn't a key press
+        game_pad.write_scr(SCR_HW_SI);
+        assert!(!game_pad.cycles(HARDWARE_READ_CYCLES));
+        assert_eq!(game_pad.read_sdlr(), 0x02);
+
+        game_pad.set_buttons(Buttons { right_down: true, ..Buttons::default() });
+        game_pad.write_scr(SCR_K_INT_INH | SCR_HW_SI);
+        assert!(!game_pad.cycles(HARDWARE_READ_CYCLES));
+        assert_eq!(game_pad.read_sdhr(), 0x80);
+        assert_eq!(game_pad.read_scr(), SCR_READ_SET | SCR_K_INT_INH);
+
+        game_pad.write_scr(0);
+        assert!(game_pad.cycles(1));
+    }
+}
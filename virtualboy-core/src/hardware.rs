@@ -0,0 +1,109 @@
+use super::bus::BusDevice;
+use super::game_pad::GamePad;
+use super::link::LinkPort;
+use super::timer::Timer;
+
+// The hardware control registers are 8 bits wide, one every 4 bytes, and mirrored every 256 bytes
+const REG_MASK: u32 = 0x000000ff;
+
+const REG_CCR: u32 = 0x00;
+const REG_CCSR: u32 = 0x04;
+const REG_CDTR: u32 = 0x08;
+const REG_CDRR: u32 = 0x0c;
+const REG_SDLR: u32 = 0x10;
+const REG_SDHR: u32 = 0x14;
+const REG_TLR: u32 = 0x18;
+const REG_THR: u32 = 0x1c;
+const REG_TCR: u32 = 0x20;
+const REG_WCR: u32 = 0x24;
+const REG_SCR: u32 = 0x28;
+
+// WCR selects a single wait state for cartridge ROM and the cartridge expansion bus, otherwise they take two
+const WCR_ROM1W: u8 = 1 << 0;
+const WCR_EXP1W: u8 = 1 << 1;
+// Bits 2-7 of WCR always read back as set
+const WCR_READ_SET: u8 = 0xfc;
+
+pub struct Hardware {
+    pub game_pad: GamePad,
+    pub timer: Timer,
+    pub link: LinkPort,
+
+    reg_wcr: u8,
+}
+
+impl Hardware {
+    pub fn new() -> Self {
+        Hardware {
+            game_pad: GamePad::new(),
+            timer: Timer::new(),
+            link: LinkPort::new(),
+
+            reg_wcr: 0,
+        }
+    }
+
+    pub fn rom_wait_states(&self) -> usize {
+        if (self.reg_wcr & WCR_ROM1W) != 0 { 1 } else { 2 }
+    }
+
+    pub fn expansion_wait_states(&self) -> usize {
+        if (self.reg_wcr & WCR_EXP1W) != 0 { 1 } else { 2 }
+    }
+
+    fn read_register(&self, addr: u32) -> Option<u8> {
+        match addr & REG_MASK {
+            REG_CCR => Some(self.link.read_ccr()),
+            REG_CCSR => Some(self.link.read_ccsr()),
+            REG_CDTR => Some(self.link.read_cdtr()),
+            REG_CDRR => Some(self.link.read_cdrr()),
+            REG_SDLR => Some(self.game_pad.read_sdlr()),
+            REG_SDHR => Some(self.game_pad.read_sdhr()),
+            REG_TLR => Some(self.timer.read_tlr()),
+            REG_THR => Some(self.timer.read_thr()),
+            REG_TCR => Some(self.timer.read_tcr()),
+            REG_WCR => Some(self.reg_wcr | WCR_READ_SET),
+            REG_SCR => Some(self.game_pad.read_scr()),
+            _ => None,
+        }
+    }
+}
+
+impl BusDevice for Hardware {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.read_register(addr).unwrap_or_else(|| {
+            println!("WARN: Reading from unknown hardware register [0x{:08x}]", addr);
+            0
+        })
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        self.read_byte(addr) as u16
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        match addr & REG_MASK {
+            REG_CCR => self.link.write_ccr(val),
+            REG_CCSR => self.link.write_ccsr(val),
+            REG_CDTR => self.link.write_cdtr(val),
+            REG_TLR => self.timer.write_tlr(val),
+            REG_THR => self.timer.write_thr(val),
+            REG_TCR => self.timer.write_tcr(val),
+            REG_WCR => self.reg_wcr = val & (WCR_ROM1W | WCR_EXP1W),
+            REG_SCR => self.game_pad.write_scr(val),
+            REG_CDRR | REG_SDLR | REG_SDHR => {
+                println!("WARN: Write to read only hardware register [0x{:08x}] = 0x{:02x}", addr, val);
+            }
+            _ => println!("WARN: Write to unknown hardware register [0x{:08x}] = 0x{:02x}", addr, val),
+        }
+    }
+
+    // Only the low byte of the data bus is connected
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        self.write_byte(addr, val as u8);
+    }
+
+    fn peek(&self, addr: u32) -> u8 {
+        self.read_register(addr).unwrap_or(0)
+    }
+}
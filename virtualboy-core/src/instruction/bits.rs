@@ -80,4 +80,29 @@ pub const OPCODE_SYSREG_PSW: u16 = 5;
 pub const OPCODE_SYSREG_PIR: u16 = 6;
 pub const OPCODE_SYSREG_TKCW: u16 = 7;
 pub const OPCODE_SYSREG_CHCW: u16 = 24;
-pub const OPCODE_SYSREG_ADTRE: u16 = 25;
\ No newline at end of file
+pub const OPCODE_SYSREG_ADTRE: u16 = 25;
+pub const OPCODE_SUBOP_CMPF_S: u16 = 0b000000;
+pub const OPCODE_SUBOP_CVT_WS: u16 = 0b000010;
+pub const OPCODE_SUBOP_CVT_SW: u16 = 0b000011;
+pub const OPCODE_SUBOP_ADDF_S: u16 = 0b000100;
+pub const OPCODE_SUBOP_SUBF_S: u16 = 0b000101;
+pub const OPCODE_SUBOP_MULF_S: u16 = 0b000110;
+pub const OPCODE_SUBOP_DIVF_S: u16 = 0b000111;
+pub const OPCODE_SUBOP_XB: u16 = 0b001000;
+pub const OPCODE_SUBOP_XH: u16 = 0b001001;
+pub const OPCODE_SUBOP_REV: u16 = 0b001010;
+pub const OPCODE_SUBOP_TRNC_SW: u16 = 0b001011;
+pub const OPCODE_SUBOP_MPYHW: u16 = 0b001100;
+
+pub const OPCODE_SUBOP_SCH0BSU: u16 = 0b00000;
+pub const OPCODE_SUBOP_SCH0BSD: u16 = 0b00001;
+pub const OPCODE_SUBOP_SCH1BSU: u16 = 0b00010;
+pub const OPCODE_SUBOP_SCH1BSD: u16 = 0b00011;
+pub const OPCODE_SUBOP_ORBSU: u16 = 0b01000;
+pub const OPCODE_SUBOP_ANDBSU: u16 = 0b01001;
+pub const OPCODE_SUBOP_XORBSU: u16 = 0b01010;
+pub const OPCODE_SUBOP_MOVBSU: u16 = 0b01011;
+pub const OPCODE_SUBOP_ORNBSU: u16 = 0b01100;
+pub const OPCODE_SUBOP_ANDNBSU: u16 = 0b01101;
+pub const OPCODE_SUBOP_XORNBSU: u16 = 0b01110;
+pub const OPCODE_SUBOP_NOTBSU: u16 = 0b01111;
@@ -24,12 +24,15 @@ impl Display for Instruction {
             Instruction::Illegal => write!(f, "ILLEGAL OPCODE"),
             Instruction::FormatI(Opcode::Jmp, reg1, _) => write!(f, "{} [r{}]", Opcode::Jmp, reg1),
             Instruction::FormatI(opcode, reg1, reg2) => write!(f, "{} r{} r{}", opcode, reg1, reg2),
+            Instruction::FormatII(opcode, _, _) if is_bit_string(opcode) => write!(f, "{}", opcode),
             Instruction::FormatII(opcode, reg2, imm5) => write!(f, "{} {} r{}", opcode, imm5, reg2),
             Instruction::FormatIII(opcode, disp9) => write!(f, "{} {}", opcode, disp9),
             Instruction::FormatIV(opcode, disp26) => write!(f, "{} {}", opcode, disp26),
             Instruction::FormatV(opcode, reg1, reg2, imm16) => write!(f, "{} 0x{:04x} r{} r{}", opcode, imm16, reg1, reg2),
             Instruction::FormatVI(opcode, reg1, reg2, disp16) => write!(f, "{} {}[gpr{}] r{}", opcode, disp16, reg1, reg2),
-            _ => panic!("Display not implemented for {:?}", self),
+            Instruction::FormatVII(Opcode::XB, _, _, reg2) => write!(f, "{} r{}", Opcode::XB, reg2),
+            Instruction::FormatVII(Opcode::XH, _, _, reg2) => write!(f, "{} r{}", Opcode::XH, reg2),
+            Instruction::FormatVII(opcode, _, reg1, reg2) => write!(f, "{} r{} r{}", opcode, reg1, reg2),
         }
     }
 }
@@ -90,7 +93,24 @@ pub fn from_halfwords(a: u16, b: u16) -> Instruction {
             OPCODE_BITS_LDSR => format_ii(Opcode::Ldsr, a, b),
             OPCODE_BITS_STSR => format_ii(Opcode::Stsr, a, b),
             OPCODE_BITS_SEI => format_ii(Opcode::Sei, a, b),
-            OPCODE_BITS_BS => unimplemented!(),
+            OPCODE_BITS_BS => {
+                let subop = a & 0x1f;
+                match subop {
+                    OPCODE_SUBOP_SCH0BSU => format_ii(Opcode::Sch0BSU, a, b),
+                    OPCODE_SUBOP_SCH0BSD => format_ii(Opcode::Sch0BSD, a, b),
+                    OPCODE_SUBOP_SCH1BSU => format_ii(Opcode::Sch1BSU, a, b),
+                    OPCODE_SUBOP_SCH1BSD => format_ii(Opcode::Sch1BSD, a, b),
+                    OPCODE_SUBOP_ORBSU => format_ii(Opcode::OrBSU, a, b),
+                    OPCODE_SUBOP_ANDBSU => format_ii(Opcode::AndBSU, a, b),
+                    OPCODE_SUBOP_XORBSU => format_ii(Opcode::XorBSU, a, b),
+                    OPCODE_SUBOP_MOVBSU => format_ii(Opcode::MovBSU, a, b),
+                    OPCODE_SUBOP_ORNBSU => format_ii(Opcode::OrNBSU, a, b),
+                    OPCODE_SUBOP_ANDNBSU => format_ii(Opcode::AndNBSU, a, b),
+                    OPCODE_SUBOP_XORNBSU => format_ii(Opcode::XorNBSU, a, b),
+                    OPCODE_SUBOP_NOTBSU => format_ii(Opcode::NotBSU, a, b),
+                    _ => Instruction::Illegal,
+                }
+            }
             OPCODE_BITS_MOVEA => format_v(Opcode::MovEa, a, b),
             OPCODE_BITS_ADDI => format_v(Opcode::AddI, a, b),
             OPCODE_BITS_JR => format_iv(Opcode::Jr, a, b),
@@ -111,13 +131,37 @@ pub fn from_halfwords(a: u16, b: u16) -> Instruction {
             OPCODE_BITS_IN_W => format_vi(Opcode::InW, a, b),
             OPCODE_BITS_OUT_B => format_vi(Opcode::OutB, a, b),
             OPCODE_BITS_OUT_H => format_vi(Opcode::OutH, a, b),
-            OPCODE_BITS_FP => unimplemented!(),
+            OPCODE_BITS_FP => {
+                let subop = b >> 10;
+                match subop {
+                    OPCODE_SUBOP_CMPF_S => format_vii(Opcode::CmpFS, a, b),
+                    OPCODE_SUBOP_CVT_WS => format_vii(Opcode::CvtWS, a, b),
+                    OPCODE_SUBOP_CVT_SW => format_vii(Opcode::CvtSW, a, b),
+                    OPCODE_SUBOP_ADDF_S => format_vii(Opcode::AddFS, a, b),
+                    OPCODE_SUBOP_SUBF_S => format_vii(Opcode::SubFS, a, b),
+                    OPCODE_SUBOP_MULF_S => format_vii(Opcode::MulFS, a, b),
+                    OPCODE_SUBOP_DIVF_S => format_vii(Opcode::DivFS, a, b),
+                    OPCODE_SUBOP_XB => format_vii(Opcode::XB, a, b),
+                    OPCODE_SUBOP_XH => format_vii(Opcode::XH, a, b),
+                    OPCODE_SUBOP_REV => format_vii(Opcode::Rev, a, b),
+                    OPCODE_SUBOP_TRNC_SW => format_vii(Opcode::TrncSW, a, b),
+                    OPCODE_SUBOP_MPYHW => format_vii(Opcode::MpyHw, a, b),
+                    _ => Instruction::Illegal,
+                }
+            }
             OPCODE_BITS_OUT_W => format_vi(Opcode::OutW, a, b),
             _ => Instruction::Illegal,
         }
     }
 }
 
+fn is_bit_string(opcode: &Opcode) -> bool {
+    matches!(opcode,
+        Opcode::Sch0BSU | Opcode::Sch0BSD | Opcode::Sch1BSU | Opcode::Sch1BSD |
+        Opcode::OrBSU | Opcode::AndBSU | Opcode::XorBSU | Opcode::MovBSU |
+        Opcode::OrNBSU | Opcode::AndNBSU | Opcode::XorNBSU | Opcode::NotBSU)
+}
+
 fn format_i(opcode: Opcode, a: u16, _b: u16) -> Instruction {
     let reg1 = a & 0x1f;
     let reg2 = (a >> 5) & 0x1f;
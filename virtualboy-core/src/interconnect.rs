@@ -1,172 +1,420 @@
-use super::ram::Ram;
+use std::io;
+use std::path::Path;
+
+use super::bus::{BusDevice, Unmapped};
+use super::game_pad::Buttons;
+use super::hardware::Hardware;
+use super::link::LinkCable;
+use super::ram::{Ram, RamInit};
 use super::rom::Rom;
-use super::vip::Vip;
+use super::sram::{self, Sram, MAX_SRAM_SIZE};
+use super::vip::{Eye, Vip};
 use super::vsu::Vsu;
 
-#[allow(dead_code)]
 pub struct Interconnect {
     vip: Vip,
     vsu: Vsu,
+    hardware: Hardware,
+    unused: Unmapped,
+    cart_expansion: Unmapped,
     sys_wram: Ram,
+    cart_ram: Sram,
     rom: Rom,
+}
+
+// The CPU drives 27 address lines, the top 3 of which select one of eight 16MiB regions. Each device is mirrored
+// throughout its region.
+const ADDR_MASK: u32 = 0x07ffffff;
+const REGION_SHIFT: u32 = 24;
+const REGION_OFFSET_MASK: u32 = 0x00ffffff;
+
+// 64KiB of WRAM mirrored throughout its 16MiB region
+const SWRAM_SIZE: u32 = 0x00010000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Vip,
+    Vsu,
+    Hardware,
+    Unused,
+    CartExpansion,
+    Wram,
+    CartRam,
+    Rom,
+}
 
-    reg_lcr: u8,
-    reg_alr: u8,
-    reg_ltd: u8,
-    reg_lrd: u8,
-    reg_gpil: u8,
-    reg_gpih: u8,
-    reg_tcrl: u8,
-    reg_tcrh: u8,
-    reg_tcr: u8,
-    reg_wcr: u8,
-    reg_gpicr: u8,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WaitStates {
+    Fixed(usize),
+    // Set by WCR's ROM1W bit
+    Rom,
+    // Set by WCR's EXP1W bit
+    Expansion,
 }
 
-const VIP_START: u32 = 0x00000000;
-const VIP_END: u32 = 0x00ffffff;
-const VSU_START: u32 = 0x01000000;
-const VSU_END: u32 = 0x01ffffff;
-const HARDWARE_LINK_CTRL: u32 = 0x02000000;
-const HARDWARE_AUX_LINK: u32 = 0x02000004;
-const HARDWARE_LINK_SEND: u32 = 0x02000008;
-const HARDWARE_LINK_RECV: u32 = 0x0200000C;
-const HARDWARE_GAME_PAD_LOW: u32 = 0x02000010;
-const HARDWARE_GAME_PAD_HIGH: u32 = 0x02000014;
-const HARDWARE_TIMER_RELOAD_HIGH: u32 = 0x02000018;
-const HARDWARE_TIMER_RELOAD_LOW: u32 = 0x0200001C;
-const HARDWARE_TIMER_CTRL: u32 = 0x02000020;
-const HARDWARE_WAIT_CTRL: u32 = 0x02000024;
-const HARDWARE_GAME_PAD_CTRL: u32 = 0x02000028;
-
-const UNUSED_START:u32 = 0x0200002C;
-const UNUSED_END:u32 = 0x03ffffff;
-
-const CART_EXPANSION_START: u32 = 0x04000000;
-const CART_EXPANSION_END: u32 = 0x04ffffff;
-const SWRAM_START: u32 = 0x05000000;
-const SWRAM_END: u32 = 0x05ffffff;
-
-const ROM_START: u32 = 0x07000000;
-const ROM_END: u32 = 0x07ffffff;
+const MEMORY_MAP: [(Region, WaitStates); 8] = [
+    (Region::Vip, WaitStates::Fixed(1)),            // 0x00000000
+    (Region::Vsu, WaitStates::Fixed(1)),            // 0x01000000
+    (Region::Hardware, WaitStates::Fixed(0)),       // 0x02000000
+    (Region::Unused, WaitStates::Fixed(0)),         // 0x03000000
+    (Region::CartExpansion, WaitStates::Expansion), // 0x04000000
+    (Region::Wram, WaitStates::Fixed(0)),           // 0x05000000
+    (Region::CartRam, WaitStates::Expansion),       // 0x06000000
+    (Region::Rom, WaitStates::Rom),                 // 0x07000000
+];
+
+const INTERRUPT_GAME_PAD: u16 = 0xfe00;
+const INTERRUPT_TIMER: u16 = 0xfe10;
+const INTERRUPT_LINK: u16 = 0xfe30;
+const INTERRUPT_VIP: u16 = 0xfe40;
+
+// Splits an address into the region it falls in and its offset within that region
+fn decode(addr: u32) -> ((Region, WaitStates), u32) {
+    let addr = addr & ADDR_MASK;
+    (MEMORY_MAP[(addr >> REGION_SHIFT) as usize], addr & REGION_OFFSET_MASK)
+}
 
 impl Interconnect {
     pub fn new(rom: Rom) -> Self {
+        let sram_size = sram::default_sram_size(&rom.game_code());
         Interconnect {
             vip: Vip::new(),
             vsu: Vsu::new(),
-            sys_wram: Ram::new(64*1024*1024),
+            hardware: Hardware::new(),
+            unused: Unmapped::new("unused memory"),
+            cart_expansion: Unmapped::new("cartridge expansion"),
+            sys_wram: Ram::new(SWRAM_SIZE),
+            cart_ram: Sram::new(sram_size),
             rom,
+        }
+    }
+
+    pub fn cycles(&mut self, cycles: usize) -> Option<u16> {
+        self.vsu.cycles(cycles);
+        let vip_interrupt = self.vip.cycles(cycles);
+        let link_interrupt = self.hardware.link.cycles(cycles);
+        let timer_interrupt = self.hardware.timer.cycles(cycles);
+        let game_pad_interrupt = self.hardware.game_pad.cycles(cycles);
 
-            reg_lcr: 0,
-            reg_alr: 0,
-            reg_ltd: 0,
-            reg_lrd: 0,
-            reg_gpil: 0,
-            reg_gpih: 0,
-            reg_tcrl: 0,
-            reg_tcrh: 0,
-            reg_tcr: 0,
-            reg_wcr: 0,
-            reg_gpicr: 0,
+        // Only the highest level interrupt being requested is passed on to the CPU
+        if vip_interrupt {
+            Some(INTERRUPT_VIP)
+        } else if link_interrupt {
+            Some(INTERRUPT_LINK)
+        } else if timer_interrupt {
+            Some(INTERRUPT_TIMER)
+        } else if game_pad_interrupt {
+            Some(INTERRUPT_GAME_PAD)
+        } else {
+            None
         }
     }
 
-    pub fn cycles(&mut self, _cycles: usize) -> Option<u16> {
-        None
+    pub fn connect_link(&mut self, cable: Box<dyn LinkCable + Send>) {
+        self.hardware.link.connect(cable);
     }
 
-    pub fn read_byte(&self, addr: u32) -> u8 {
-        let addr = addr & 0x07ffffff;
-        match addr {
-            VIP_START..=VIP_END => self.vip.read_byte(addr - VIP_START),
-            VSU_START..=VSU_END => unimplemented!(),   // VSU
-            0x02000000..=0x02ffffff => unimplemented!(),   // Hardware Control Registers
-            0x03000000..=0x03ffffff => unimplemented!(),   // Not Used
-            CART_EXPANSION_START..=CART_EXPANSION_END => unimplemented!(),   // Cartridge Expansion
-            SWRAM_START..=SWRAM_END => self.sys_wram.read_byte(addr - SWRAM_START),   // System WRAM
-            0x06000000..=0x06ffffff => unimplemented!(),   // Cartridge RAM
-            ROM_START..=ROM_END => self.rom.read_byte(addr - ROM_START),   // Cartridge ROM
-            _ => unreachable!(),
-        }
+    pub fn disconnect_link(&mut self) {
+        self.hardware.link.disconnect();
     }
 
-    pub fn read_halfword(&self, addr: u32) -> u16 {
-        let addr = addr & 0x07ffffff;
-        let addr = addr & 0xfffffffe;
-        match addr {
-            VIP_START..=VIP_END => self.vip.read_halfword(addr - VIP_START),
-            VSU_START..=VSU_END => unimplemented!(),   // VSU
-            0x02000000..=0x02ffffff => unimplemented!(),   // Hardware Control Registers
-            0x03000000..=0x03ffffff => unimplemented!(),   // Not Used
-            CART_EXPANSION_START..=CART_EXPANSION_END => unimplemented!(),   // Cartridge Expansion
-            SWRAM_START..=SWRAM_END => self.sys_wram.read_halfword(addr - SWRAM_START),   // System WRAM
-            0x06000000..=0x06ffffff => unimplemented!(),   // Cartridge RAM
-            ROM_START..=ROM_END => self.rom.read_halfword(addr - ROM_START),   // Cartridge ROM
-            _ => unreachable!(),
+    pub fn init_wram(&mut self, init: RamInit) {
+        self.sys_wram.init(init);
+    }
+
+    pub fn set_sram_size(&mut self, size: u32) -> io::Result<()> {
+        if !size.is_power_of_two() || size > MAX_SRAM_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid SRAM size"));
         }
+
+        self.cart_ram.resize(size);
+        Ok(())
+    }
+
+    pub fn attach_save_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.cart_ram.attach_save_file(path)
+    }
+
+    pub fn flush_save_file(&mut self) -> io::Result<()> {
+        self.cart_ram.flush()
     }
 
-    pub fn read_word(&self, addr: u32) -> u32 {
-        (self.read_halfword(addr) as u32) | ((self.read_halfword(addr + 2) as u32) << 16)
-    }
-
-    pub fn write_byte(&mut self, addr:u32, val: u8) {
-        match addr {
-            VIP_START..=VIP_END => unimplemented!(),
-            VSU_START..=VSU_END => self.vsu.write_byte(addr - VSU_START, val),
-            HARDWARE_LINK_CTRL => unimplemented!(),
-            HARDWARE_AUX_LINK => unimplemented!(),
-            HARDWARE_LINK_SEND => unimplemented!(),
-            HARDWARE_LINK_RECV => unimplemented!(),
-            HARDWARE_GAME_PAD_LOW => unimplemented!(),
-            HARDWARE_GAME_PAD_HIGH => unimplemented!(),
-            HARDWARE_TIMER_RELOAD_HIGH => {
-                println!("WARN: Write to timer reload high register not fully supported");
-                self.reg_tcrh = val;
-            }
-            HARDWARE_TIMER_RELOAD_LOW => {
-                println!("WARN: Write to timer reload high register not fully supported");
-                self.reg_tcrl = val;
-            }
-            HARDWARE_TIMER_CTRL => {
-                println!("WARN: Write to timer control register not fully supported");
-                self.reg_tcrl = val
-            },
-            HARDWARE_WAIT_CTRL => unimplemented!(),
-            HARDWARE_GAME_PAD_CTRL => {
-                println!("WARN: Write to game pad control register not fully supported");
-                self.reg_gpicr = val
-            }
-            UNUSED_START..=UNUSED_END => {
-                println!("WARN: Write to unused portion of memory");
-            },
-            CART_EXPANSION_START..=CART_EXPANSION_END => {
-                println!("WARN: Writing to cartridge expansion unimplemented {:08x}={}", addr, val);
-            }
-            SWRAM_START..=SWRAM_END => self.sys_wram.write_byte(addr - SWRAM_START, val),   // System WRAM
-            0x06000000..=0x06ffffff => unimplemented!(),   // Cartridge RAM
-            ROM_START..=ROM_END => unimplemented!(),   // Cartridge ROM
-            _ => unreachable!(),
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.hardware.game_pad.set_buttons(buttons);
+    }
+
+    pub fn set_low_battery(&mut self, low_battery: bool) {
+        self.hardware.game_pad.set_low_battery(low_battery);
+    }
+
+    pub fn frame(&self, eye: Eye) -> &[u8] {
+        self.vip.frame(eye)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.vsu.sample_rate()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) -> io::Result<()> {
+        self.vsu.set_sample_rate(sample_rate)
+    }
+
+    pub fn samples_available(&self) -> usize {
+        self.vsu.samples_available()
+    }
+
+    pub fn read_samples(&mut self, out: &mut [i16]) -> usize {
+        self.vsu.read_samples(out)
+    }
+
+    // Extra cycles a data access spends on the bus, on top of an instruction's base timing
+    pub fn access_cycles(&self, addr: u32, size: u32) -> usize {
+        let ((_, wait_states), _) = decode(addr);
+        let wait_states = match wait_states {
+            WaitStates::Fixed(wait_states) => wait_states,
+            WaitStates::Rom => self.hardware.rom_wait_states(),
+            WaitStates::Expansion => self.hardware.expansion_wait_states(),
+        };
+
+        // The data bus is 16 bits wide so word accesses take two bus cycles
+        let bus_cycles = if size == 4 { 2 } else { 1 };
+        bus_cycles * (1 + wait_states) - 1
+    }
+
+    fn device(&self, region: Region) -> &dyn BusDevice {
+        match region {
+            Region::Vip => &self.vip,
+            Region::Vsu => &self.vsu,
+            Region::Hardware => &self.hardware,
+            Region::Unused => &self.unused,
+            Region::CartExpansion => &self.cart_expansion,
+            Region::Wram => &self.sys_wram,
+            Region::CartRam => &self.cart_ram,
+            Region::Rom => &self.rom,
         }
     }
 
-    pub fn write_halfword(&mut self, addr:u32, val: u16) {
-        match addr {
-            VIP_START..=VIP_END => self.vip.write_halfword(addr - VIP_START, val),
-            VSU_START..=VSU_END => self.vsu.write_halfword(addr - VSU_START, val),   // VSU
-            0x02000000..=0x02ffffff => unimplemented!(),   // Hardware Control Registers
-            0x03000000..=0x03ffffff => unimplemented!(),   // Not Used
-            CART_EXPANSION_START..=CART_EXPANSION_END => unimplemented!(),   // Cartridge Expansion
-            SWRAM_START..=SWRAM_END => self.sys_wram.write_halfword(addr - SWRAM_START, val),   // System WRAM
-            0x06000000..=0x06ffffff => unimplemented!(),   // Cartridge RAM
-            ROM_START..=ROM_END => unimplemented!(),   // Cartridge ROM
-            _ => unreachable!(),
+    fn device_mut(&mut self, region: Region) -> &mut dyn BusDevice {
+        match region {
+            Region::Vip => &mut self.vip,
+            Region::Vsu => &mut self.vsu,
+            Region::Hardware => &mut self.hardware,
+            Region::Unused => &mut self.unused,
+            Region::CartExpansion => &mut self.cart_expansion,
+            Region::Wram => &mut self.sys_wram,
+            Region::CartRam => &mut self.cart_ram,
+            Region::Rom => &mut self.rom,
         }
     }
 
+    pub fn read_byte(&mut self, addr: u32) -> u8 {
+        let ((region, _), offset) = decode(addr);
+        self.device_mut(region).read_byte(offset)
+    }
+
+    pub fn read_halfword(&mut self, addr: u32) -> u16 {
+        let ((region, _), offset) = decode(addr & 0xfffffffe);
+        self.device_mut(region).read_halfword(offset)
+    }
+
+    pub fn read_word(&mut self, addr: u32) -> u32 {
+        let ((region, _), offset) = decode(addr & 0xfffffffc);
+        self.device_mut(region).read_word(offset)
+    }
+
+    pub fn write_byte(&mut self, addr: u32, val: u8) {
+        let ((region, _), offset) = decode(addr);
+        self.device_mut(region).write_byte(offset, val);
+    }
+
+    pub fn write_halfword(&mut self, addr: u32, val: u16) {
+        let ((region, _), offset) = decode(addr & 0xfffffffe);
+        self.device_mut(region).write_halfword(offset, val);
+    }
+
     pub fn write_word(&mut self, addr: u32, val: u32) {
-        self.write_halfword(addr, val as _);
-        self.write_halfword(addr + 2, (val >> 16) as _);
+        let ((region, _), offset) = decode(addr & 0xfffffffc);
+        self.device_mut(region).write_word(offset, val);
+    }
+
+    pub fn peek_byte(&self, addr: u32) -> u8 {
+        let ((region, _), offset) = decode(addr);
+        self.device(region).peek(offset)
+    }
+
+    pub fn peek_halfword(&self, addr: u32) -> u16 {
+        let addr = addr & 0xfffffffe;
+        (self.peek_byte(addr) as u16) | ((self.peek_byte(addr + 1) as u16) << 8)
+    }
+
+    pub fn peek_word(&self, addr: u32) -> u32 {
+        let addr = addr & 0xfffffffc;
+        (self.peek_halfword(addr) as u32) | ((self.peek_halfword(addr + 2) as u32) << 16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::InProcessLink;
+    use crate::rom::MIN_ROM_SIZE;
+
+    fn interconnect() -> Interconnect {
+        Interconnect::new(Rom::from_bytes(&[0; MIN_ROM_SIZE]).unwrap())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn wram_mirrors_every_64k() {
+        let mut interconnect = interconnect();
+        interconnect.write_word(0x05000000, 0x12345678);
+        assert_eq!(interconnect.read_word(0x05010000), 0x12345678);
+        assert_eq!(interconnect.read_word(0x05ff0000), 0x12345678);
+
+        interconnect.write_halfword(0x05ff0002, 0xabcd);
+        assert_eq!(interconnect.read_halfword(0x05000002), 0xabcd);
+    }
+
+    #[test]
+    fn wram_init() {
+        let mut interconnect = interconnect();
+        interconnect.init_wram(RamInit::Fill(0x5a));
+        assert_eq!(interconnect.read_word(0x05000000), 0x5a5a5a5a);
+        assert_eq!(interconnect.read_byte(0x0500ffff), 0x5a);
+    }
+
+    #[test]
+    fn timer_interrupt() {
+        let mut interconnect = interconnect();
+        interconnect.write_byte(0x02000018, 0x01);
+        interconnect.write_byte(0x0200001c, 0x00);
+        interconnect.write_byte(0x02000020, 0x19);
+
+        assert_eq!(interconnect.cycles(399), None);
+        assert_eq!(interconnect.cycles(1), Some(INTERRUPT_TIMER));
+        assert_eq!(interconnect.read_byte(0x02000020), 0xff);
+
+        // Acknowledging with Z-STAT-CLR drops the request
+        interconnect.write_byte(0x02000020, 0x1d);
+        assert_eq!(interconnect.cycles(1), None);
+    }
+
+    #[test]
+    fn link_interrupt() {
+        let (cable_a, cable_b) = InProcessLink::pair();
+        let mut interconnect_a = interconnect();
+        let mut interconnect_b = interconnect();
+        interconnect_a.connect_link(Box::new(cable_a));
+        interconnect_b.connect_link(Box::new(cable_b));
+
+        interconnect_b.write_byte(0x02000008, 0x42);
+        interconnect_b.write_byte(0x02000000, 0x14);
+        interconnect_a.write_byte(0x02000008, 0x24);
+        interconnect_a.write_byte(0x02000000, 0x04);
+        assert_eq!(interconnect_a.read_byte(0x02000000), 0x6b);
+
+        assert_eq!(interconnect_a.cycles(3200), Some(INTERRUPT_LINK));
+        assert_eq!(interconnect_b.cycles(1), Some(INTERRUPT_LINK));
+        assert_eq!(interconnect_a.read_byte(0x0200000c), 0x42);
+        assert_eq!(interconnect_b.read_byte(0x0200000c), 0x24);
+        assert_eq!(interconnect_a.read_byte(0x02000000), 0x69);
+        assert_eq!(interconnect_b.read_byte(0x02000000), 0x79);
+    }
+
+    #[test]
+    fn sram_size_limits() {
+        let mut interconnect = interconnect();
+        assert!(interconnect.set_sram_size(2 * MAX_SRAM_SIZE).is_err());
+        assert!(interconnect.set_sram_size(0x3000).is_err());
+
+        // The largest SRAM is more than even addresses can reach, so nothing in the region mirrors
+        interconnect.set_sram_size(MAX_SRAM_SIZE).unwrap();
+        interconnect.write_byte(0x06000000, 0x11);
+        interconnect.write_byte(0x06fffffe, 0x22);
+        assert_eq!(interconnect.read_byte(0x06000000), 0x11);
+        assert_eq!(interconnect.read_byte(0x06fffffe), 0x22);
+    }
+
+    #[test]
+    fn upper_address_bits_ignored() {
+        let mut interconnect = interconnect();
+        interconnect.write_word(0xfd001234, 0xdeadbeef);
+        assert_eq!(interconnect.read_word(0x05001234), 0xdeadbeef);
+        assert_eq!(interconnect.read_halfword(0x0d001236), 0xdead);
+        assert_eq!(interconnect.read_byte(0x15001234), 0xef);
+
+        // VIP VRAM at the bottom of the address space
+        interconnect.write_halfword(0x08000010, 0x1357);
+        assert_eq!(interconnect.read_halfword(0x00000010), 0x1357);
+        assert_eq!(interconnect.peek_halfword(0xf8000010), 0x1357);
+
+        // And the timer's reload registers in the hardware region
+        interconnect.write_byte(0xfa000018, 0x34);
+        assert_eq!(interconnect.read_byte(0x02000018), 0x34);
+        assert_eq!(decode(0xffffffff), decode(0x07ffffff));
+    }
+
+    #[test]
+    fn words_split_into_halfwords() {
+        let mut interconnect = interconnect();
+        interconnect.write_word(0x05000100, 0x12345678);
+        assert_eq!(interconnect.read_halfword(0x05000100), 0x5678);
+        assert_eq!(interconnect.read_halfword(0x05000102), 0x1234);
+
+        interconnect.write_halfword(0x05000100, 0xabcd);
+        assert_eq!(interconnect.read_word(0x05000100), 0x1234abcd);
+
+        // Word accesses are aligned down to a word boundary
+        interconnect.write_word(0x05000206, 0xcafef00d);
+        assert_eq!(interconnect.read_halfword(0x05000204), 0xf00d);
+        assert_eq!(interconnect.read_word(0x05000207), 0xcafef00d);
+
+        interconnect.write_word(0x00000200, 0x87654321);
+        assert_eq!(interconnect.read_halfword(0x00000200), 0x4321);
+        assert_eq!(interconnect.read_halfword(0x00000202), 0x8765);
+    }
+
+    #[test]
+    fn hardware_registers_mirror_every_256_bytes() {
+        let mut interconnect = interconnect();
+        interconnect.write_byte(0x02000118, 0x78);
+        interconnect.write_byte(0x02ffff1c, 0x56);
+        assert_eq!(interconnect.read_byte(0x02000018), 0x78);
+        assert_eq!(interconnect.read_byte(0x0200001c), 0x56);
+        assert_eq!(interconnect.read_byte(0x02abcd18), 0x78);
+
+        // WCR through a mirror changes the wait states
+        assert_eq!(interconnect.access_cycles(0x07000000, 2), 2);
+        interconnect.write_byte(0x02000524, 0x01);
+        assert_eq!(interconnect.access_cycles(0x07000000, 2), 1);
+        assert_eq!(interconnect.read_byte(0x02000024), 0xfd);
+    }
+
+    #[test]
+    fn peek_matches_read_without_side_effects() {
+        let mut interconnect = interconnect();
+        interconnect.init_wram(RamInit::Random(1));
+        interconnect.write_byte(0x02000018, 0x02);
+        interconnect.write_byte(0x02000020, 0x19);
+        interconnect.write_halfword(0x0005f822, 0x0002);
+        interconnect.write_byte(0x06000000, 0x99);
+        interconnect.cycles(400 * 3);
+
+        let addrs = [
+            0x00000000, 0x0005f800, 0x0005f820, 0x0005f830, 0x0005f840,
+            0x01000000, 0x02000014, 0x02000018, 0x02000020, 0x02000028,
+            0x03000000, 0x04000000, 0x05000000, 0x0500abcd, 0x06000000, 0x06000001, 0x07000000,
+        ];
+        for &addr in addrs.iter() {
+            let peeked = interconnect.peek_byte(addr);
+            assert_eq!(peeked, interconnect.peek_byte(addr));
+            assert_eq!(peeked, interconnect.read_byte(addr), "0x{:08x}", addr);
+        }
+
+        let word = interconnect.peek_word(0x05000010);
+        assert_eq!(interconnect.peek_halfword(0x05000012), (word >> 16) as u16);
+        assert_eq!(interconnect.read_word(0x05000010), word);
+
+        // Peeking at the timer's pending zero interrupt leaves it pending
+        assert_eq!(interconnect.cycles(0), Some(INTERRUPT_TIMER));
+    }
+}
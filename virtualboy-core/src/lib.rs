@@ -6,8 +6,16 @@ pub mod instruction;
 pub mod rom;
 pub mod virtualboy;
 
+mod audio;
+mod bus;
+mod cache;
+mod game_pad;
+mod hardware;
 mod interconnect;
+mod link;
 mod ram;
+mod sram;
+mod timer;
 mod v810;
 mod vip;
 mod vsu;
\ No newline at end of file
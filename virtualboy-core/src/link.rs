@@ -0,0 +1,327 @@
+use std::sync::{Arc, Mutex};
+
+const CCR_C_STAT: u8 = 1 << 1;
+const CCR_C_START: u8 = 1 << 2;
+const CCR_C_CLK_SEL: u8 = 1 << 4;
+const CCR_C_INT_INH: u8 = 1 << 7;
+// Bits 0, 3, 5 and 6 of CCR always read back as set
+const CCR_READ_SET: u8 = 0x69;
+
+const CCSR_CC_RD: u8 = 1 << 0;
+const CCSR_CC_WR: u8 = 1 << 1;
+const CCSR_CC_SIG: u8 = 1 << 2;
+const CCSR_CC_SMP: u8 = 1 << 3;
+const CCSR_CC_INT_INH: u8 = 1 << 7;
+// Bits 5 and 6 of CCSR always read back as set
+const CCSR_READ_SET: u8 = 0x60;
+
+// The internal clock shifts the 8 data bits out at roughly 50kHz
+const TRANSFER_CYCLES: usize = 3200;
+// With nothing on the other end the data line floats high
+const DISCONNECTED_DATA: u8 = 0xff;
+
+// The far end of the link port. A transfer is clocked by the unit using its internal clock, the unit on the
+// external clock offers its byte and waits to be clocked.
+pub trait LinkCable {
+    // Makes data available to be exchanged the next time the other end clocks a transfer
+    fn wait_for_clock(&mut self, data: u8);
+    // Exchanges data with the other end if it's waiting, returning the byte it sent back
+    fn clock(&mut self, data: u8) -> Option<u8>;
+    // The byte received by a waiting transfer once the other end has clocked it
+    fn take_received(&mut self) -> Option<u8>;
+    fn set_com_cnt(&mut self, level: bool);
+    fn other_com_cnt(&self) -> bool;
+}
+
+#[derive(Default)]
+struct InProcessEnd {
+    waiting: Option<u8>,
+    received: Option<u8>,
+    com_cnt: bool,
+}
+
+// A cable between two VirtualBoys running in the same process, created in connected pairs
+pub struct InProcessLink {
+    ends: Arc<Mutex<[InProcessEnd; 2]>>,
+    side: usize,
+}
+
+impl InProcessLink {
+    pub fn pair() -> (InProcessLink, InProcessLink) {
+        let ends = Arc::new(Mutex::new([InProcessEnd::default(), InProcessEnd::default()]));
+        (InProcessLink { ends: ends.clone(), side: 0 }, InProcessLink { ends, side: 1 })
+    }
+}
+
+impl LinkCable for InProcessLink {
+    fn wait_for_clock(&mut self, data: u8) {
+        let mut ends = self.ends.lock().unwrap();
+        ends[self.side].waiting = Some(data);
+        ends[self.side].received = None;
+    }
+
+    fn clock(&mut self, data: u8) -> Option<u8> {
+        let mut ends = self.ends.lock().unwrap();
+        let other = &mut ends[self.side ^ 1];
+        let sent = other.waiting.take()?;
+        other.received = Some(data);
+        Some(sent)
+    }
+
+    fn take_received(&mut self) -> Option<u8> {
+        self.ends.lock().unwrap()[self.side].received.take()
+    }
+
+    fn set_com_cnt(&mut self, level: bool) {
+        self.ends.lock().unwrap()[self.side].com_cnt = level;
+    }
+
+    fn other_com_cnt(&self) -> bool {
+        self.ends.lock().unwrap()[self.side ^ 1].com_cnt
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Internal(usize),
+    External,
+}
+
+pub struct LinkPort {
+    cable: Option<Box<dyn LinkCable + Send>>,
+
+    reg_external_clock: bool,
+    reg_transfer_interrupt_inhibit: bool,
+    reg_com_cnt_out: bool,
+    reg_com_cnt_signal: bool,
+    reg_com_cnt_sample: bool,
+    reg_com_cnt_interrupt_inhibit: bool,
+    reg_cdtr: u8,
+    reg_cdrr: u8,
+
+    transfer: Option<Transfer>,
+    transfer_interrupt: bool,
+    com_cnt_interrupt: bool,
+}
+
+impl LinkPort {
+    pub fn new() -> Self {
+        LinkPort {
+            cable: None,
+
+            reg_external_clock: false,
+            reg_transfer_interrupt_inhibit: false,
+            reg_com_cnt_out: true,
+            reg_com_cnt_signal: false,
+            reg_com_cnt_sample: false,
+            reg_com_cnt_interrupt_inhibit: false,
+            reg_cdtr: 0,
+            reg_cdrr: 0,
+
+            transfer: None,
+            transfer_interrupt: false,
+            com_cnt_interrupt: false,
+        }
+    }
+
+    pub fn connect(&mut self, mut cable: Box<dyn LinkCable + Send>) {
+        cable.set_com_cnt(self.reg_com_cnt_out);
+        self.cable = Some(cable);
+    }
+
+    pub fn disconnect(&mut self) {
+        self.cable = None;
+    }
+
+    // Advances any transfer in progress, returning whether the communication interrupt is being requested
+    pub fn cycles(&mut self, cycles: usize) -> bool {
+        match self.transfer {
+            Some(Transfer::Internal(cycles_left)) if cycles_left <= cycles => {
+                let data = self.reg_cdtr;
+                let received = self.cable.as_mut().and_then(|cable| cable.clock(data));
+                self.finish_transfer(received.unwrap_or(DISCONNECTED_DATA));
+            }
+            Some(Transfer::Internal(cycles_left)) => self.transfer = Some(Transfer::Internal(cycles_left - cycles)),
+            Some(Transfer::External) => {
+                if let Some(received) = self.cable.as_mut().and_then(|cable| cable.take_received()) {
+                    self.finish_transfer(received);
+                }
+            }
+            None => {}
+        }
+
+        if self.reg_com_cnt_sample && self.com_cnt() == self.reg_com_cnt_signal {
+            self.com_cnt_interrupt = true;
+        }
+
+        (self.transfer_interrupt && !self.reg_transfer_interrupt_inhibit)
+            || (self.com_cnt_interrupt && !self.reg_com_cnt_interrupt_inhibit)
+    }
+
+    fn finish_transfer(&mut self, received: u8) {
+        self.transfer = None;
+        self.reg_cdrr = received;
+        self.transfer_interrupt = true;
+    }
+
+    // COM-CNT is open collector, either end can pull it low
+    #[allow(clippy::unnecessary_map_or)]
+    fn com_cnt(&self) -> bool {
+        let other = self.cable.as_ref().map_or(true, |cable| cable.other_com_cnt());
+        self.reg_com_cnt_out && other
+    }
+
+    pub fn read_ccr(&self) -> u8 {
+        let mut val = CCR_READ_SET;
+        val |= if self.transfer.is_some() { CCR_C_STAT } else { 0 };
+        val |= if self.reg_external_clock { CCR_C_CLK_SEL } else { 0 };
+        val |= if self.reg_transfer_interrupt_inhibit { CCR_C_INT_INH } else { 0 };
+        val
+    }
+
+    pub fn read_ccsr(&self) -> u8 {
+        let mut val = CCSR_READ_SET;
+        val |= if self.com_cnt() { CCSR_CC_RD } else { 0 };
+        val |= if self.reg_com_cnt_out { CCSR_CC_WR } else { 0 };
+        val |= if self.reg_com_cnt_signal { CCSR_CC_SIG } else { 0 };
+        val |= if self.reg_com_cnt_sample { CCSR_CC_SMP } else { 0 };
+        val |= if self.reg_com_cnt_interrupt_inhibit { CCSR_CC_INT_INH } else { 0 };
+        val
+    }
+
+    pub fn read_cdtr(&self) -> u8 {
+        self.reg_cdtr
+    }
+
+    pub fn read_cdrr(&self) -> u8 {
+        self.reg_cdrr
+    }
+
+    pub fn write_ccr(&mut self, val: u8) {
+        // Setting C-INT-INH also acknowledges a pending interrupt
+        self.reg_transfer_interrupt_inhibit = (val & CCR_C_INT_INH) != 0;
+        if self.reg_transfer_interrupt_inhibit {
+            self.transfer_interrupt = false;
+        }
+
+        if self.transfer.is_some() {
+            return;
+        }
+        self.reg_external_clock = (val & CCR_C_CLK_SEL) != 0;
+
+        if (val & CCR_C_START) != 0 {
+            if self.reg_external_clock {
+                let data = self.reg_cdtr;
+                if let Some(cable) = self.cable.as_mut() {
+                    cable.wait_for_clock(data);
+                }
+                self.transfer = Some(Transfer::External);
+            } else {
+                self.transfer = Some(Transfer::Internal(TRANSFER_CYCLES));
+            }
+        }
+    }
+
+    pub fn write_ccsr(&mut self, val: u8) {
+        self.reg_com_cnt_interrupt_inhibit = (val & CCSR_CC_INT_INH) != 0;
+        if self.reg_com_cnt_interrupt_inhibit {
+            self.com_cnt_interrupt = false;
+        }
+
+        self.reg_com_cnt_signal = (val & CCSR_CC_SIG) != 0;
+        self.reg_com_cnt_sample = (val & CCSR_CC_SMP) != 0;
+        self.reg_com_cnt_out = (val & CCSR_CC_WR) != 0;
+        let com_cnt_out = self.reg_com_cnt_out;
+        if let Some(cable) = self.cable.as_mut() {
+            cable.set_com_cnt(com_cnt_out);
+        }
+    }
+
+    pub fn write_cdtr(&mut self, val: u8) {
+        if self.transfer.is_none() {
+            self.reg_cdtr = val;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connected_ports() -> (LinkPort, LinkPort) {
+        let (cable_a, cable_b) = InProcessLink::pair();
+        let mut port_a = LinkPort::new();
+        let mut port_b = LinkPort::new();
+        port_a.connect(Box::new(cable_a));
+        port_b.connect(Box::new(cable_b));
+        (port_a, port_b)
+    }
+
+    #[test]
+    fn internal_clock_transfer() {
+        let (mut port_a, mut port_b) = connected_ports();
+
+        port_b.write_cdtr(0x5a);
+        port_b.write_ccr(CCR_C_CLK_SEL | CCR_C_START);
+        assert_ne!(port_b.read_ccr() & CCR_C_STAT, 0);
+        // Nothing happens on the external clock end until the other end clocks the transfer
+        assert!(!port_b.cycles(TRANSFER_CYCLES * 2));
+
+        port_a.write_cdtr(0xa5);
+        port_a.write_ccr(CCR_C_START);
+        assert_eq!(port_a.read_ccr(), CCR_READ_SET | CCR_C_STAT);
+        // CDTR is locked while the transfer is in progress
+        port_a.write_cdtr(0x00);
+        assert_eq!(port_a.read_cdtr(), 0xa5);
+
+        assert!(!port_a.cycles(TRANSFER_CYCLES - 1));
+        assert!(port_a.cycles(1));
+        assert_eq!(port_a.read_ccr() & CCR_C_STAT, 0);
+        assert_eq!(port_a.read_cdrr(), 0x5a);
+
+        assert!(port_b.cycles(1));
+        assert_eq!(port_b.read_ccr() & CCR_C_STAT, 0);
+        assert_eq!(port_b.read_cdrr(), 0xa5);
+
+        // C-INT-INH acknowledges the interrupt
+        port_a.write_ccr(CCR_C_INT_INH);
+        assert!(!port_a.cycles(1));
+    }
+
+    #[test]
+    fn disconnected_transfer_reads_high() {
+        let mut port = LinkPort::new();
+        port.write_cdtr(0x12);
+        port.write_ccr(CCR_C_START);
+        assert!(port.cycles(TRANSFER_CYCLES));
+        assert_eq!(port.read_cdrr(), DISCONNECTED_DATA);
+    }
+
+    #[test]
+    fn com_cnt_handshake() {
+        let (mut port_a, mut port_b) = connected_ports();
+        assert_ne!(port_a.read_ccsr() & CCSR_CC_RD, 0);
+        assert_ne!(port_b.read_ccsr() & CCSR_CC_RD, 0);
+
+        // B waits for COM-CNT to go low
+        port_b.write_ccsr(CCSR_CC_WR | CCSR_CC_SMP);
+        assert!(!port_b.cycles(1));
+
+        // A pulls the line low, which both ends see
+        port_a.write_ccsr(0);
+        assert_eq!(port_a.read_ccsr(), CCSR_READ_SET);
+        assert_eq!(port_b.read_ccsr() & CCSR_CC_RD, 0);
+        assert!(port_b.cycles(1));
+
+        // B acknowledges and A releases the line
+        port_b.write_ccsr(CCSR_CC_WR | CCSR_CC_INT_INH);
+        assert!(!port_b.cycles(1));
+        port_a.write_ccsr(CCSR_CC_WR);
+        assert_ne!(port_a.read_ccsr() & CCSR_CC_RD, 0);
+        assert_ne!(port_b.read_ccsr() & CCSR_CC_RD, 0);
+
+        // Either end holding the line low keeps it low
+        port_b.write_ccsr(0);
+        assert_eq!(port_a.read_ccsr() & CCSR_CC_RD, 0);
+    }
+}
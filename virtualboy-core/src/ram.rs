@@ -1,3 +1,14 @@
+use super::bus::BusDevice;
+
+// How RAM is filled at power on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RamInit {
+    Zero,
+    Fill(u8),
+    // Pseudo-random contents generated from the given seed, to shake out reads of uninitialised memory
+    Random(u32),
+}
+
 pub struct Ram {
     data: Box<[u8]>,
     size: u32,
@@ -17,6 +28,29 @@ impl Ram {
         }
     }
 
+    pub fn init(&mut self, init: RamInit) {
+        match init {
+            RamInit::Zero => self.fill(0),
+            RamInit::Fill(val) => self.fill(val),
+            RamInit::Random(seed) => {
+                // xorshift32, which never leaves a state of zero
+                let mut state = if seed == 0 { 0x2545f491 } else { seed };
+                for byte in self.data.iter_mut() {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    *byte = state as u8;
+                }
+            }
+        }
+    }
+
+    fn fill(&mut self, val: u8) {
+        for byte in self.data.iter_mut() {
+            *byte = val;
+        }
+    }
+
     pub fn read_byte(&self, addr: u32) -> u8 {
         let addr = self.mask_addr(addr);
 
@@ -46,4 +80,73 @@ impl Ram {
         let mask = self.size - 1;
         (addr & mask) as usize
     }
-}
\ No newline at end of file
+}
+
+impl BusDevice for Ram {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        Ram::read_byte(self, addr)
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        Ram::read_halfword(self, addr)
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        Ram::write_byte(self, addr, val);
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        Ram::write_halfword(self, addr, val);
+    }
+
+    fn peek(&self, addr: u32) -> u8 {
+        Ram::read_byte(self, addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(ram: &Ram) -> Vec<u8> {
+        (0..ram.size).map(|addr| Ram::read_byte(ram, addr)).collect()
+    }
+
+    #[test]
+    fn init_fill() {
+        let mut ram = Ram::new(0x100);
+        ram.init(RamInit::Fill(0xa5));
+        assert!(contents(&ram).iter().all(|&val| val == 0xa5));
+
+        ram.init(RamInit::Zero);
+        assert!(contents(&ram).iter().all(|&val| val == 0x00));
+    }
+
+    #[test]
+    fn init_random() {
+        let mut ram = Ram::new(0x100);
+        ram.init(RamInit::Random(1234));
+        let first = contents(&ram);
+
+        // The same seed always gives the same contents
+        ram.init(RamInit::Zero);
+        ram.init(RamInit::Random(1234));
+        assert_eq!(contents(&ram), first);
+
+        ram.init(RamInit::Random(5678));
+        assert_ne!(contents(&ram), first);
+
+        // A seed of zero still produces varied contents
+        ram.init(RamInit::Random(0));
+        let zero_seeded = contents(&ram);
+        assert!(zero_seeded.iter().any(|&val| val != zero_seeded[0]));
+    }
+
+    #[test]
+    fn mirroring() {
+        let mut ram = Ram::new(0x100);
+        Ram::write_halfword(&mut ram, 0x0102, 0xbeef);
+        assert_eq!(Ram::read_halfword(&ram, 0x0002), 0xbeef);
+        assert_eq!(Ram::read_byte(&ram, 0xff03), 0xbe);
+    }
+}
@@ -7,6 +7,8 @@ use std::io::{self, Read, Error, ErrorKind};
 use std::fs::File;
 use std::path::Path;
 
+use super::bus::BusDevice;
+
 use encoding::Encoding;
 use encoding::all::WINDOWS_31J;
 use encoding::types::DecoderTrap;
@@ -76,6 +78,7 @@ impl Rom {
         format!("1.{}", game_ver_byte)
     }
 
+    #[allow(clippy::unnecessary_cast)]
     pub fn read_byte(&self, addr: u32) -> u8 {
         let addr = self.mask_addr(addr);
         self.data[addr as usize]
@@ -90,4 +93,26 @@ impl Rom {
         let mask = self.size() - 1;
         addr as usize & mask
     }
-}
\ No newline at end of file
+}
+
+impl BusDevice for Rom {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        Rom::read_byte(self, addr)
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        Rom::read_halfword(self, addr)
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        println!("WARN: Writing to ROM [0x{:08x}] = 0x{:02x}", addr, val);
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        println!("WARN: Writing to ROM [0x{:08x}] = 0x{:04x}", addr, val);
+    }
+
+    fn peek(&self, addr: u32) -> u8 {
+        Rom::read_byte(self, addr)
+    }
+}
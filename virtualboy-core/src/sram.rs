@@ -0,0 +1,225 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use super::bus::BusDevice;
+use super::ram::Ram;
+
+// Used for any game missing from SRAM_SIZES
+pub const DEFAULT_SRAM_SIZE: u32 = 8 * 1024;
+// The whole of the 16MiB cartridge RAM region. Only even addresses reach the SRAM, so the CPU sees no more than the
+// first 8MiB of one this large.
+pub const MAX_SRAM_SIZE: u32 = 16 * 1024 * 1024;
+
+// The ROM header doesn't say whether a cartridge has SRAM or how much, so the size is looked up by the header's
+// game code
+const SRAM_SIZES: &[(&str, u32)] = &[
+    ("VGPJ", 8 * 1024), // Galactic Pinball
+    ("VGPE", 8 * 1024),
+    ("VWCJ", 8 * 1024), // Virtual Boy Wario Land
+    ("VWCE", 8 * 1024),
+    ("VMTJ", 8 * 1024), // Mario's Tennis
+    ("VMTE", 8 * 1024),
+];
+
+// The SRAM size for the game with the given game code
+pub fn default_sram_size(game_code: &str) -> u32 {
+    SRAM_SIZES.iter()
+        .find(|&&(code, _)| code == game_code)
+        .map_or(DEFAULT_SRAM_SIZE, |&(_, size)| size)
+}
+
+pub const SAVE_FILE_EXTENSION: &str = "srm";
+
+// The save file that sits next to a ROM
+pub fn save_file_path<P: AsRef<Path>>(rom_path: P) -> PathBuf {
+    rom_path.as_ref().with_extension(SAVE_FILE_EXTENSION)
+}
+
+// Cartridge RAM sits on the low 8 bits of the data bus, so each byte of SRAM appears at an even address. Odd
+// addresses and the high byte of a halfword see the same byte mirrored onto the upper data lines.
+pub struct Sram {
+    data: Ram,
+    size: u32,
+
+    save_file: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl Sram {
+    pub fn new(size: u32) -> Self {
+        Sram {
+            data: Ram::new(size),
+            size,
+
+            save_file: None,
+            dirty: false,
+        }
+    }
+
+    // Backs the SRAM with a save file, loading its contents if it already exists. An existing file decides the
+    // size of the SRAM, otherwise the current size is kept.
+    pub fn attach_save_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        if path.exists() {
+            let mut contents = Vec::new();
+            File::open(&path)?.read_to_end(&mut contents)?;
+
+            let size = contents.len();
+            if size == 0 || size > MAX_SRAM_SIZE as usize {
+                return Err(Error::new(ErrorKind::InvalidData, "Invalid save file size"));
+            }
+
+            *self = Sram::new((size as u32).next_power_of_two());
+            for (addr, &val) in contents.iter().enumerate() {
+                self.data.write_byte(addr as u32, val);
+            }
+        }
+
+        self.save_file = Some(path);
+        self.dirty = false;
+        Ok(())
+    }
+
+    // Changes the size of the SRAM, keeping as much of its contents as still fit along with any save file, which
+    // is rewritten at the new size on the next flush
+    pub fn resize(&mut self, size: u32) {
+        let mut resized = Sram::new(size);
+        for addr in 0..size.min(self.size) {
+            resized.data.write_byte(addr, self.data.read_byte(addr));
+        }
+        resized.save_file = self.save_file.take();
+        resized.dirty = resized.save_file.is_some() && (self.dirty || size != self.size);
+
+        *self = resized;
+    }
+
+    // Writes the SRAM out to its save file if anything has changed since it was last saved
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let (Some(path), true) = (self.save_file.as_ref(), self.dirty) {
+            let contents: Vec<u8> = (0..self.size).map(|addr| self.data.read_byte(addr)).collect();
+            fs::write(path, contents)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl BusDevice for Sram {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.data.read_byte(addr >> 1)
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        let val = self.data.read_byte(addr >> 1) as u16;
+        val | (val << 8)
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        // A byte written to an odd address is driven onto the high data lines, which the SRAM can't see
+        if (addr & 0x01) == 0 {
+            self.data.write_byte(addr >> 1, val);
+            self.dirty = true;
+        }
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        self.data.write_byte(addr >> 1, val as u8);
+        self.dirty = true;
+    }
+
+    fn peek(&self, addr: u32) -> u8 {
+        self.data.read_byte(addr >> 1)
+    }
+}
+
+impl Drop for Sram {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            println!("WARN: Failed to write save file: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A save file path unique to the test, cleared of anything left over from an earlier run
+    fn temp_save_file(name: &str) -> PathBuf {
+        let file_name = format!("virtualboy-{}-{}.{}", name, std::process::id(), SAVE_FILE_EXTENSION);
+        let path = std::env::temp_dir().join(file_name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn sram_size_by_game_code() {
+        assert_eq!(default_sram_size("VWCE"), 8 * 1024);
+        assert_eq!(default_sram_size("ZZZZ"), DEFAULT_SRAM_SIZE);
+        assert!(SRAM_SIZES.iter().all(|&(_, size)| size.is_power_of_two() && size <= MAX_SRAM_SIZE));
+    }
+
+    #[test]
+    fn save_survives_reload() {
+        let path = temp_save_file("save-survives-reload");
+
+        let mut sram = Sram::new(DEFAULT_SRAM_SIZE);
+        sram.attach_save_file(&path).unwrap();
+        sram.write_byte(0x0000, 0x12);
+        sram.write_halfword(0x0002, 0xff34);
+        // Odd addresses can't be written
+        sram.write_byte(0x0005, 0x56);
+        drop(sram);
+
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(contents.len(), DEFAULT_SRAM_SIZE as usize);
+        assert_eq!(&contents[0..3], &[0x12, 0x34, 0x00]);
+
+        let mut sram = Sram::new(DEFAULT_SRAM_SIZE);
+        sram.attach_save_file(&path).unwrap();
+        assert_eq!(sram.read_byte(0x0000), 0x12);
+        assert_eq!(sram.read_byte(0x0003), 0x34);
+        assert_eq!(sram.read_halfword(0x0002), 0x3434);
+        assert_eq!(sram.read_byte(0x0004), 0x00);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_file_decides_size() {
+        let path = temp_save_file("save-file-decides-size");
+        fs::write(&path, vec![0xaa; 0x300]).unwrap();
+
+        let mut sram = Sram::new(DEFAULT_SRAM_SIZE);
+        sram.attach_save_file(&path).unwrap();
+        assert_eq!(sram.size, 0x400);
+        assert_eq!(sram.read_byte(0x05fe), 0xaa);
+        assert_eq!(sram.read_byte(0x0600), 0x00);
+        // Mirrored every 1KiB
+        assert_eq!(sram.read_byte(0x0800), 0xaa);
+
+        drop(sram);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resize_keeps_save_file() {
+        let path = temp_save_file("resize-keeps-save-file");
+
+        let mut sram = Sram::new(DEFAULT_SRAM_SIZE);
+        sram.attach_save_file(&path).unwrap();
+        sram.write_byte(0x0000, 0x78);
+        sram.resize(2 * DEFAULT_SRAM_SIZE);
+        assert_eq!(sram.read_byte(0x0000), 0x78);
+        sram.flush().unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(contents.len(), 2 * DEFAULT_SRAM_SIZE as usize);
+        assert_eq!(contents[0], 0x78);
+
+        drop(sram);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+const TCR_T_ENB: u8 = 1 << 0;
+const TCR_Z_STAT: u8 = 1 << 1;
+const TCR_Z_STAT_CLR: u8 = 1 << 2;
+const TCR_TIM_Z_INT: u8 = 1 << 3;
+const TCR_T_CLK_SEL: u8 = 1 << 4;
+// Bits 2, 5, 6 and 7 of TCR always read back as set
+const TCR_READ_SET: u8 = 0xe4;
+
+// The counter ticks every 100us, or every 20us with T-CLK-SEL set
+const SLOW_TICK_CYCLES: usize = 2000;
+const FAST_TICK_CYCLES: usize = 400;
+
+pub struct Timer {
+    reg_reload: u16,
+    reg_enable: bool,
+    reg_zero_status: bool,
+    reg_interrupt_enable: bool,
+    reg_fast_clock: bool,
+
+    counter: u16,
+    tick_clock: usize,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Timer {
+            reg_reload: 0,
+            reg_enable: false,
+            reg_zero_status: false,
+            reg_interrupt_enable: false,
+            reg_fast_clock: false,
+
+            counter: 0,
+            tick_clock: 0,
+        }
+    }
+
+    // Counts the timer down, returning whether the zero interrupt is being requested
+    pub fn cycles(&mut self, cycles: usize) -> bool {
+        if self.reg_enable {
+            let tick_cycles = if self.reg_fast_clock { FAST_TICK_CYCLES } else { SLOW_TICK_CYCLES };
+            self.tick_clock += cycles;
+            while self.tick_clock >= tick_cycles {
+                self.tick_clock -= tick_cycles;
+                self.tick();
+            }
+        }
+
+        self.reg_zero_status && self.reg_interrupt_enable
+    }
+
+    // The counter reloads on the tick after it reaches zero, so it fires every reload + 1 ticks
+    fn tick(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.reg_reload;
+        } else {
+            self.counter -= 1;
+            if self.counter == 0 {
+                self.reg_zero_status = true;
+            }
+        }
+    }
+
+    pub fn read_tlr(&self) -> u8 {
+        self.counter as u8
+    }
+
+    pub fn read_thr(&self) -> u8 {
+        (self.counter >> 8) as u8
+    }
+
+    pub fn read_tcr(&self) -> u8 {
+        let mut val = TCR_READ_SET;
+        val |= if self.reg_enable { TCR_T_ENB } else { 0 };
+        val |= if self.reg_zero_status { TCR_Z_STAT } else { 0 };
+        val |= if self.reg_interrupt_enable { TCR_TIM_Z_INT } else { 0 };
+        val |= if self.reg_fast_clock { TCR_T_CLK_SEL } else { 0 };
+        val
+    }
+
+    // Writing either half of the reload value also resets the counter to it
+    pub fn write_tlr(&mut self, val: u8) {
+        self.reg_reload = (self.reg_reload & 0xff00) | val as u16;
+        self.counter = self.reg_reload;
+    }
+
+    pub fn write_thr(&mut self, val: u8) {
+        self.reg_reload = (self.reg_reload & 0x00ff) | ((val as u16) << 8);
+        self.counter = self.reg_reload;
+    }
+
+    pub fn write_tcr(&mut self, val: u8) {
+        let enable = (val & TCR_T_ENB) != 0;
+        if enable && !self.reg_enable {
+            self.tick_clock = 0;
+        }
+        self.reg_enable = enable;
+        self.reg_interrupt_enable = (val & TCR_TIM_Z_INT) != 0;
+        self.reg_fast_clock = (val & TCR_T_CLK_SEL) != 0;
+
+        if (val & TCR_Z_STAT_CLR) != 0 {
+            self.reg_zero_status = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(timer: &mut Timer, reload: u16, tcr: u8) {
+        timer.write_tlr(reload as u8);
+        timer.write_thr((reload >> 8) as u8);
+        timer.write_tcr(TCR_T_ENB | tcr);
+    }
+
+    fn counter(timer: &Timer) -> u16 {
+        ((timer.read_thr() as u16) << 8) | timer.read_tlr() as u16
+    }
+
+    #[test]
+    fn counts_down_to_zero() {
+        let mut timer = Timer::new();
+        start(&mut timer, 0x0103, TCR_T_CLK_SEL | TCR_TIM_Z_INT);
+        assert_eq!(counter(&timer), 0x0103);
+
+        assert!(!timer.cycles(FAST_TICK_CYCLES * 0x0103 - 1));
+        assert_eq!(counter(&timer), 0x0001);
+        assert_eq!(timer.read_tcr() & TCR_Z_STAT, 0);
+
+        assert!(timer.cycles(1));
+        assert_eq!(counter(&timer), 0x0000);
+        assert_eq!(timer.read_tcr(), TCR_READ_SET | TCR_T_ENB | TCR_Z_STAT | TCR_TIM_Z_INT | TCR_T_CLK_SEL);
+    }
+
+    #[test]
+    fn reloads_every_reload_plus_one_ticks() {
+        let mut timer = Timer::new();
+        start(&mut timer, 3, 0);
+        timer.cycles(SLOW_TICK_CYCLES * 3);
+        assert_eq!(counter(&timer), 0);
+
+        timer.write_tcr(TCR_T_ENB | TCR_Z_STAT_CLR);
+        timer.cycles(SLOW_TICK_CYCLES);
+        assert_eq!(counter(&timer), 3);
+        assert_eq!(timer.read_tcr() & TCR_Z_STAT, 0);
+
+        timer.cycles(SLOW_TICK_CYCLES * 3 - 1);
+        assert_eq!(timer.read_tcr() & TCR_Z_STAT, 0);
+        timer.cycles(1);
+        assert_ne!(timer.read_tcr() & TCR_Z_STAT, 0);
+        assert_eq!(counter(&timer), 0);
+    }
+
+    #[test]
+    fn zero_status_clear() {
+        let mut timer = Timer::new();
+        start(&mut timer, 1, TCR_TIM_Z_INT);
+        assert!(timer.cycles(SLOW_TICK_CYCLES));
+
+        // Z-STAT and the interrupt stay up until cleared
+        assert!(timer.cycles(SLOW_TICK_CYCLES));
+        timer.write_tcr(TCR_T_ENB | TCR_TIM_Z_INT | TCR_Z_STAT_CLR);
+        assert!(!timer.cycles(0));
+        assert_eq!(timer.read_tcr() & TCR_Z_STAT, 0);
+
+        // Clearing the interrupt enable masks the interrupt but leaves Z-STAT set
+        timer.cycles(SLOW_TICK_CYCLES);
+        timer.write_tcr(TCR_T_ENB);
+        assert!(!timer.cycles(0));
+        assert_ne!(timer.read_tcr() & TCR_Z_STAT, 0);
+    }
+
+    #[test]
+    fn stopped_timer_holds() {
+        let mut timer = Timer::new();
+        timer.write_tlr(5);
+        timer.cycles(SLOW_TICK_CYCLES * 10);
+        assert_eq!(counter(&timer), 5);
+
+        // Ticks are timed from when the timer is enabled
+        timer.cycles(SLOW_TICK_CYCLES / 2);
+        timer.write_tcr(TCR_T_ENB);
+        timer.cycles(SLOW_TICK_CYCLES - 1);
+        assert_eq!(counter(&timer), 5);
+        timer.cycles(1);
+        assert_eq!(counter(&timer), 4);
+    }
+}
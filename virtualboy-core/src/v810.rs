@@ -1,6 +1,28 @@
+use super::cache::InstructionCache;
 use super::interconnect::Interconnect;
 use super::instruction;
 
+const EXCEPTION_FP_RESERVED_OPERAND: u16 = 0xff60;
+const EXCEPTION_FP_OVERFLOW: u16 = 0xff64;
+const EXCEPTION_FP_ZERO_DIVISION: u16 = 0xff68;
+const EXCEPTION_FP_INVALID_OPERATION: u16 = 0xff70;
+const EXCEPTION_ZERO_DIVISION: u16 = 0xff80;
+const EXCEPTION_ILLEGAL_OPCODE: u16 = 0xff90;
+const EXCEPTION_TRAP: u16 = 0xffa0;
+const EXCEPTION_ADDRESS_TRAP: u16 = 0xffc0;
+const EXCEPTION_DUPLEXED: u16 = 0xffd0;
+const EXCEPTION_INTERRUPT_BASE: u16 = 0xfe00;
+
+// Cycles taken by a bit string instruction for each word it works through
+const BIT_STRING_SEARCH_CYCLES: usize = 51;
+const BIT_STRING_TRANSFER_CYCLES: usize = 38;
+
+// Processor ID for the NEC V810 as fitted to the Virtual Boy
+const PIR_VALUE: u32 = 0x00005346;
+// The V810's task control word is hard-wired to round to nearest with all FP exceptions enabled
+const TKCW_VALUE: u32 = 0x000000e0;
+const PSW_MASK: u32 = 0x000ff3ff;
+
 #[allow(dead_code)] // FIXME - remove once we have a more complete implementation that uses all the registers
 #[derive(Default)]
 pub struct V810 {
@@ -33,6 +55,10 @@ pub struct V810 {
     reg_psw_nmi_pending: bool,
     reg_psw_interrupt_mask_level: u8,
 
+    halted: bool,
+    last_was_load: bool,
+
+    pub cache: InstructionCache,
 }
 
 impl V810 {
@@ -46,40 +72,37 @@ impl V810 {
         self.reg_eipsw = 0xdeadbeef;
         self.reg_fepc = 0xdeadbeef;
         self.reg_fepsw = 0xdeadbeef;
+        self.reg_tkcw = TKCW_VALUE;
+        self.reg_adtre = 0x00000000;
         self.set_ecr(0x0000, 0xfff0);
         self.set_reg_psw(0x00008000);
+        self.halted = false;
+        self.last_was_load = false;
+        self.cache = InstructionCache::new();
         for i in 1..32 {
             self.reg_gpr[i] = 0xdeadbeef;
         }
     }
 
     pub fn step(&mut self, interconnect: &mut Interconnect) -> usize {
-        let first_halfword = interconnect.read_halfword(self.reg_pc);
+        if self.halted {
+            return 1;
+        }
+
+        let address_trap = self.reg_psw_address_trap_enable && self.reg_pc == self.reg_adtre;
+
+        let mut fetch_cycles = 0;
+        let first_halfword = self.fetch_halfword(interconnect, self.reg_pc, &mut fetch_cycles);
         let mut next_pc = self.reg_pc.wrapping_add(2);
-        let cycles = 1; // FIXME - should be based on instruction run
+        let mut cycles;
+        let mut is_load = false;
 
         if (first_halfword >> 13) == instruction::OPCODE_BITS_BCOND_PREFIX {
             let opbits = first_halfword >> 9;
 
-            let take_branch = match opbits {
-                instruction::OPCODE_BITS_BCOND_BV => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BC => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BZ => self.reg_psw_zero,
-                instruction::OPCODE_BITS_BCOND_BNH => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BN => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BR => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BLT => self.reg_psw_sign ^ self.reg_psw_overflow,
-                instruction::OPCODE_BITS_BCOND_BLE => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BNV => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BNC => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BNZ => !self.reg_psw_zero,
-                instruction::OPCODE_BITS_BCOND_BH => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BP => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_NOP => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BGE => unimplemented!(),
-                instruction::OPCODE_BITS_BCOND_BGT => unimplemented!(),
-                _ => unreachable!(),
-            };
+            let take_branch = self.condition(opbits & 0x0f);
+            // Taken branches flush the pipeline
+            cycles = if take_branch { 3 } else { 1 };
 
             if take_branch {
                 let disp = ((((first_halfword as i16) << 7) >> 7) as u32) & 0xfffffffe;
@@ -102,7 +125,7 @@ impl V810 {
             }
             macro_rules! format_iv {
                 ($f:expr) => ({
-                    let second_halfword = interconnect.read_halfword(next_pc);
+                    let second_halfword = self.fetch_halfword(interconnect, next_pc, &mut fetch_cycles);
                     next_pc = next_pc.wrapping_add(2);
 
                     let disp26 = ((first_halfword as u32) << 16) | (second_halfword as u32);
@@ -113,7 +136,7 @@ impl V810 {
             }
             macro_rules! format_v {
                 ($f:expr) => ({
-                    let second_halfword = interconnect.read_halfword(next_pc);
+                    let second_halfword = self.fetch_halfword(interconnect, next_pc, &mut fetch_cycles);
                     next_pc = next_pc.wrapping_add(2);
 
                     let reg1 = (first_halfword & 0x1f);
@@ -122,9 +145,20 @@ impl V810 {
                     $f(imm16, reg1, reg2);
                 })
             }
+            macro_rules! format_vii {
+                ($f:expr) => ({
+                    let second_halfword = self.fetch_halfword(interconnect, next_pc, &mut fetch_cycles);
+                    next_pc = next_pc.wrapping_add(2);
+
+                    let reg1 = first_halfword & 0x1f;
+                    let reg2 = (first_halfword >> 5) & 0x1f;
+                    let subop = second_halfword >> 10;
+                    $f(subop, reg1, reg2);
+                })
+            }
             macro_rules! format_vi {
                 ($f:expr) => ({
-                    let second_halfword = interconnect.read_halfword(next_pc);
+                    let second_halfword = self.fetch_halfword(interconnect, next_pc, &mut fetch_cycles);
                     next_pc = next_pc.wrapping_add(2);
 
                     let reg1 = (first_halfword & 0x1f);
@@ -135,6 +169,7 @@ impl V810 {
             }
 
             let opbits = first_halfword >> 10;
+            cycles = opcode_cycles(opbits);
 
             match opbits {
                 instruction::OPCODE_BITS_MOV_R => format_i!(|reg1, reg2| {
@@ -146,23 +181,89 @@ impl V810 {
                     let rhs = self.reg_gpr(reg2);
                     self.add(lhs, rhs, reg2);
                 }),
-                // instruction::OPCODE_BITS_SUB=> unimplemented!(),
+                instruction::OPCODE_BITS_SUB => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg2);
+                    let rhs = self.reg_gpr(reg1);
+                    let res = self.sub(lhs, rhs);
+                    self.set_reg_gpr(reg2, res);
+                }),
                 instruction::OPCODE_BITS_CMP_R => format_i!(|reg1, reg2| {
                     let lhs = self.reg_gpr(reg2);
                     let rhs = self.reg_gpr(reg1);
                     self.sub(lhs, rhs);
                 }),
-                // instruction::OPCODE_BITS_SHL_R=> unimplemented!(),
-                // instruction::OPCODE_BITS_SHR_R=> unimplemented!(),
+                instruction::OPCODE_BITS_SHL_R => format_i!(|reg1, reg2| {
+                    let val = self.reg_gpr(reg2);
+                    let shift = self.reg_gpr(reg1);
+                    let res = self.shl(val, shift);
+                    self.set_reg_gpr(reg2, res);
+                }),
+                instruction::OPCODE_BITS_SHR_R => format_i!(|reg1, reg2| {
+                    let val = self.reg_gpr(reg2);
+                    let shift = self.reg_gpr(reg1);
+                    let res = self.shr(val, shift);
+                    self.set_reg_gpr(reg2, res);
+                }),
                 instruction::OPCODE_BITS_JMP => format_i!(|reg1, _| {
                     let dest = self.reg_gpr(reg1);
                     next_pc = dest;
                 }),
-                // instruction::OPCODE_BITS_SAR_R=> unimplemented!(),
-                // instruction::OPCODE_BITS_MUL=> unimplemented!(),
-                // instruction::OPCODE_BITS_DIV=> unimplemented!(),
-                // instruction::OPCODE_BITS_MULU=> unimplemented!(),
-                // instruction::OPCODE_BITS_DIVU=> unimplemented!(),
+                instruction::OPCODE_BITS_SAR_R => format_i!(|reg1, reg2| {
+                    let val = self.reg_gpr(reg2);
+                    let shift = self.reg_gpr(reg1);
+                    let res = self.sar(val, shift);
+                    self.set_reg_gpr(reg2, res);
+                }),
+                instruction::OPCODE_BITS_MUL => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg2) as i32 as i64;
+                    let rhs = self.reg_gpr(reg1) as i32 as i64;
+                    let res = lhs * rhs;
+                    let res_low = res as u32;
+                    let res_high = (res >> 32) as u32;
+                    self.reg_psw_overflow = res != (res_low as i32 as i64);
+                    self.set_zero_sign_flags(res_low);
+                    self.set_reg_gpr(30, res_high);
+                    self.set_reg_gpr(reg2, res_low);
+                }),
+                instruction::OPCODE_BITS_DIV => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg2) as i32;
+                    let rhs = self.reg_gpr(reg1) as i32;
+                    if rhs == 0 {
+                        next_pc = self.raise_exception(interconnect, EXCEPTION_ZERO_DIVISION, self.reg_pc);
+                        return;
+                    }
+                    let (quotient, overflow) = lhs.overflowing_div(rhs);
+                    let remainder = lhs.wrapping_rem(rhs);
+                    self.reg_psw_overflow = overflow;
+                    self.set_zero_sign_flags(quotient as u32);
+                    self.set_reg_gpr(30, remainder as u32);
+                    self.set_reg_gpr(reg2, quotient as u32);
+                }),
+                instruction::OPCODE_BITS_MULU => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg2) as u64;
+                    let rhs = self.reg_gpr(reg1) as u64;
+                    let res = lhs * rhs;
+                    let res_low = res as u32;
+                    let res_high = (res >> 32) as u32;
+                    self.reg_psw_overflow = res_high != 0;
+                    self.set_zero_sign_flags(res_low);
+                    self.set_reg_gpr(30, res_high);
+                    self.set_reg_gpr(reg2, res_low);
+                }),
+                instruction::OPCODE_BITS_DIVU => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg2);
+                    let rhs = self.reg_gpr(reg1);
+                    if rhs == 0 {
+                        next_pc = self.raise_exception(interconnect, EXCEPTION_ZERO_DIVISION, self.reg_pc);
+                        return;
+                    }
+                    let quotient = lhs / rhs;
+                    let remainder = lhs % rhs;
+                    self.reg_psw_overflow = false;
+                    self.set_zero_sign_flags(quotient);
+                    self.set_reg_gpr(30, remainder);
+                    self.set_reg_gpr(reg2, quotient);
+                }),
                 instruction::OPCODE_BITS_OR => format_i!(|reg1, reg2| {
                     let lhs = self.reg_gpr(reg1);
                     let rhs = self.reg_gpr(reg2);
@@ -179,8 +280,20 @@ impl V810 {
                     self.set_zero_sign_flags(res);
                     self.set_reg_gpr(reg2, res);
                 }),
-                // instruction::OPCODE_BITS_XOR=> unimplemented!(),
-                // instruction::OPCODE_BITS_NOT=> unimplemented!(),
+                instruction::OPCODE_BITS_XOR => format_i!(|reg1, reg2| {
+                    let lhs = self.reg_gpr(reg1);
+                    let rhs = self.reg_gpr(reg2);
+                    let res = lhs ^ rhs;
+                    self.reg_psw_overflow = false;
+                    self.set_zero_sign_flags(res);
+                    self.set_reg_gpr(reg2, res);
+                }),
+                instruction::OPCODE_BITS_NOT => format_i!(|reg1, reg2| {
+                    let res = !self.reg_gpr(reg1);
+                    self.reg_psw_overflow = false;
+                    self.set_zero_sign_flags(res);
+                    self.set_reg_gpr(reg2, res);
+                }),
                 instruction::OPCODE_BITS_MOV_I => format_ii!(|imm5, reg2| {
                     let val = sign_extend_imm5(imm5);
                     self.set_reg_gpr(reg2, val);
@@ -190,7 +303,10 @@ impl V810 {
                     let rhs = sign_extend_imm5(imm5);
                     self.add(lhs, rhs, reg2);
                 }),
-                // instruction::OPCODE_BITS_SETF=> unimplemented!(),
+                instruction::OPCODE_BITS_SETF => format_ii!(|imm5, reg2| {
+                    let val = if self.condition(imm5 & 0x0f) { 1 } else { 0 };
+                    self.set_reg_gpr(reg2, val);
+                }),
                 instruction::OPCODE_BITS_CMP_I => format_ii!(|imm5, reg2| {
                     let lhs = self.reg_gpr(reg2);
                     let rhs = sign_extend_imm5(imm5);
@@ -209,46 +325,95 @@ impl V810 {
                 instruction::OPCODE_BITS_CLI => format_ii!(|_, _| {
                     self.reg_psw_interrupt_disable = false;
                 }),
-                // instruction::OPCODE_BITS_SAR_I=> unimplemented!(),
-                // instruction::OPCODE_BITS_TRAP=> unimplemented!(),
-                // instruction::OPCODE_BITS_RETI=> unimplemented!(),
-                // instruction::OPCODE_BITS_HALT=> unimplemented!(),
+                instruction::OPCODE_BITS_SAR_I => format_ii!(|imm5, reg2| {
+                    let val = self.reg_gpr(reg2);
+                    let res = self.sar(val, imm5 as _);
+                    self.set_reg_gpr(reg2, res);
+                }),
+                instruction::OPCODE_BITS_TRAP => format_ii!(|imm5, _| {
+                    next_pc = self.raise_exception(interconnect, EXCEPTION_TRAP + imm5, next_pc);
+                }),
+                instruction::OPCODE_BITS_RETI => format_ii!(|_, _| {
+                    let (pc, psw) = if self.reg_psw_nmi_pending {
+                        (self.reg_fepc, self.reg_fepsw)
+                    } else {
+                        (self.reg_eipc, self.reg_eipsw)
+                    };
+                    next_pc = pc;
+                    self.set_reg_psw(psw);
+                }),
+                instruction::OPCODE_BITS_HALT => format_ii!(|_, _| {
+                    self.halted = true;
+                }),
                 instruction::OPCODE_BITS_LDSR => format_ii!(|imm5, reg2| {
                     let val = self.reg_gpr(reg2);
                     match imm5 {
-                        instruction::OPCODE_SYSREG_EIPC => unimplemented!(),
-                        instruction::OPCODE_SYSREG_EIPSW => unimplemented!(),
-                        instruction::OPCODE_SYSREG_FEPC => unimplemented!(),
-                        instruction::OPCODE_SYSREG_FEPSW => unimplemented!(),
+                        instruction::OPCODE_SYSREG_EIPC => self.reg_eipc = val & 0xfffffffe,
+                        instruction::OPCODE_SYSREG_EIPSW => self.reg_eipsw = val & PSW_MASK,
+                        instruction::OPCODE_SYSREG_FEPC => self.reg_fepc = val & 0xfffffffe,
+                        instruction::OPCODE_SYSREG_FEPSW => self.reg_fepsw = val & PSW_MASK,
                         instruction::OPCODE_SYSREG_ECR => println!("WARN: Attempted to write to ECR {}", val),
                         instruction::OPCODE_SYSREG_PSW => self.set_reg_psw(val),
                         instruction::OPCODE_SYSREG_PIR => println!("WARN: Attempted to write to PIR {}", val),
                         instruction::OPCODE_SYSREG_TKCW => println!("WARN: Attempted to write to TKCW {}", val),
-                        instruction::OPCODE_SYSREG_CHCW => self.set_reg_chcw(val),
-                        instruction::OPCODE_SYSREG_ADTRE => unimplemented!(),
-                        _ => unreachable!(),
+                        instruction::OPCODE_SYSREG_CHCW => self.set_reg_chcw(interconnect, val),
+                        instruction::OPCODE_SYSREG_ADTRE => self.reg_adtre = val & 0xfffffffe,
+                        _ => println!("WARN: Attempted to write to reserved system register {} {}", imm5, val),
                     }
                 }),
                 instruction::OPCODE_BITS_STSR => format_ii!(|imm5, reg2| {
                     let val = match imm5 {
-                        instruction::OPCODE_SYSREG_EIPC => unimplemented!(),
-                        instruction::OPCODE_SYSREG_EIPSW => unimplemented!(),
-                        instruction::OPCODE_SYSREG_FEPC => unimplemented!(),
-                        instruction::OPCODE_SYSREG_FEPSW => unimplemented!(),
-                        instruction::OPCODE_SYSREG_ECR => unimplemented!(),
+                        instruction::OPCODE_SYSREG_EIPC => self.reg_eipc,
+                        instruction::OPCODE_SYSREG_EIPSW => self.reg_eipsw,
+                        instruction::OPCODE_SYSREG_FEPC => self.reg_fepc,
+                        instruction::OPCODE_SYSREG_FEPSW => self.reg_fepsw,
+                        instruction::OPCODE_SYSREG_ECR => self.reg_ecr,
                         instruction::OPCODE_SYSREG_PSW => self.reg_psw(),
-                        instruction::OPCODE_SYSREG_PIR => unimplemented!(),
-                        instruction::OPCODE_SYSREG_TKCW => unimplemented!(),
-                        instruction::OPCODE_SYSREG_CHCW => unimplemented!(),
-                        instruction::OPCODE_SYSREG_ADTRE => unimplemented!(),
-                        _ => unreachable!(),
+                        instruction::OPCODE_SYSREG_PIR => PIR_VALUE,
+                        instruction::OPCODE_SYSREG_TKCW => self.reg_tkcw,
+                        instruction::OPCODE_SYSREG_CHCW => self.reg_chcw,
+                        instruction::OPCODE_SYSREG_ADTRE => self.reg_adtre,
+                        _ => {
+                            println!("WARN: Attempted to read from reserved system register {}", imm5);
+                            0
+                        }
                     };
                     self.set_reg_gpr(reg2, val);
                 }),
                 instruction::OPCODE_BITS_SEI => format_ii!(|_, _| {
                     self.reg_psw_interrupt_disable = true;
                 }),
-                // instruction::OPCODE_BITS_BS=> unimplemented!(),
+                instruction::OPCODE_BITS_BS => format_ii!(|imm5, _| {
+                    cycles = if imm5 <= instruction::OPCODE_SUBOP_SCH1BSD {
+                        BIT_STRING_SEARCH_CYCLES
+                    } else {
+                        BIT_STRING_TRANSFER_CYCLES
+                    };
+                    let complete = match imm5 {
+                        instruction::OPCODE_SUBOP_SCH0BSU => self.bit_string_search(interconnect, 0, true),
+                        instruction::OPCODE_SUBOP_SCH0BSD => self.bit_string_search(interconnect, 0, false),
+                        instruction::OPCODE_SUBOP_SCH1BSU => self.bit_string_search(interconnect, 1, true),
+                        instruction::OPCODE_SUBOP_SCH1BSD => self.bit_string_search(interconnect, 1, false),
+                        instruction::OPCODE_SUBOP_ORBSU |
+                        instruction::OPCODE_SUBOP_ANDBSU |
+                        instruction::OPCODE_SUBOP_XORBSU |
+                        instruction::OPCODE_SUBOP_MOVBSU |
+                        instruction::OPCODE_SUBOP_ORNBSU |
+                        instruction::OPCODE_SUBOP_ANDNBSU |
+                        instruction::OPCODE_SUBOP_XORNBSU |
+                        instruction::OPCODE_SUBOP_NOTBSU => self.bit_string_transfer(interconnect, imm5),
+                        _ => {
+                            next_pc = self.raise_exception(interconnect, EXCEPTION_ILLEGAL_OPCODE, self.reg_pc);
+                            return;
+                        }
+                    };
+
+                    // Bit string instructions work through at most one word per step, keeping all of their
+                    // state in r26-r30, so they can be interrupted and re-executed until they complete
+                    if !complete {
+                        next_pc = self.reg_pc;
+                    }
+                }),
                 instruction::OPCODE_BITS_MOVEA => format_v!(|imm16, reg1, reg2| {
                     let lhs = self.reg_gpr(reg1);
                     let rhs = imm16 as i16 as u32;
@@ -283,7 +448,14 @@ impl V810 {
                     self.reg_psw_zero = res == 0;
                     self.set_reg_gpr(reg2, res);
                 }),
-                // instruction::OPCODE_BITS_XORI=> unimplemented!(),
+                instruction::OPCODE_BITS_XORI => format_v!(|imm16, reg1, reg2| {
+                    let lhs = self.reg_gpr(reg1);
+                    let rhs = imm16 as u32;
+                    let res = lhs ^ rhs;
+                    self.reg_psw_overflow = false;
+                    self.set_zero_sign_flags(res);
+                    self.set_reg_gpr(reg2, res);
+                }),
                 instruction::OPCODE_BITS_MOVHI => format_v!(|imm16, reg1, reg2| {
                     let lhs = self.reg_gpr(reg1);
                     let rhs = (imm16 as u32) << 16;
@@ -291,69 +463,238 @@ impl V810 {
                 }),
                 instruction::OPCODE_BITS_LD_B => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
-                    let addr = addr & 0xfffffffc;
 
+                    cycles += self.load_cycles(interconnect, addr, 1);
+                    is_load = true;
                     let val = interconnect.read_byte(addr);
-                    self.set_reg_gpr(reg2, val as _);
+                    self.set_reg_gpr(reg2, val as i8 as _);
                 }),
                 instruction::OPCODE_BITS_LD_H => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
-                    let addr = addr & 0xfffffffc;
+                    let addr = addr & 0xfffffffe;
 
+                    cycles += self.load_cycles(interconnect, addr, 2);
+                    is_load = true;
                     let val = interconnect.read_halfword(addr);
-                    self.set_reg_gpr(reg2, val as _);
+                    self.set_reg_gpr(reg2, val as i16 as _);
                 }),
                 instruction::OPCODE_BITS_LD_W => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
                     let addr = addr & 0xfffffffc;
 
+                    cycles += self.load_cycles(interconnect, addr, 4);
+                    is_load = true;
                     let val = interconnect.read_word(addr);
                     self.set_reg_gpr(reg2, val);
                 }),
-                instruction::OPCODE_BITS_ST_B => format_vi!(|reg1, reg2, disp16| {
+                instruction::OPCODE_BITS_ST_B | instruction::OPCODE_BITS_OUT_B => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
-                    let addr = addr & 0xfffffffe;
                     let val = self.reg_gpr(reg2) as u8;
 
+                    cycles += interconnect.access_cycles(addr, 1);
                     interconnect.write_byte(addr, val);
                 }),
-                instruction::OPCODE_BITS_ST_H => format_vi!(|reg1, reg2, disp16| {
+                instruction::OPCODE_BITS_ST_H | instruction::OPCODE_BITS_OUT_H => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
                     let addr = addr & 0xfffffffe;
                     let val = self.reg_gpr(reg2) as u16;
 
+                    cycles += interconnect.access_cycles(addr, 2);
                     interconnect.write_halfword(addr, val);
                 }),
-                instruction::OPCODE_BITS_ST_W => format_vi!(|reg1, reg2, disp16| {
+                instruction::OPCODE_BITS_ST_W | instruction::OPCODE_BITS_OUT_W => format_vi!(|reg1, reg2, disp16| {
                     let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
                     let addr = addr & 0xfffffffc;
                     let val = self.reg_gpr(reg2);
 
+                    cycles += interconnect.access_cycles(addr, 4);
                     interconnect.write_word(addr, val);
                 }),
-                // instruction::OPCODE_BITS_IN_B=> unimplemented!(),
-                // instruction::OPCODE_BITS_IN_H=> unimplemented!(),
-                // instruction::OPCODE_BITS_CAXI=> unimplemented!(),
-                // instruction::OPCODE_BITS_IN_W=> unimplemented!(),
-                // instruction::OPCODE_BITS_OUT_B=> unimplemented!(),
-                // instruction::OPCODE_BITS_OUT_H=> unimplemented!(),
-                // instruction::OPCODE_BITS_FP=> unimplemented!(),
-                // instruction::OPCODE_BITS_OUT_W=> unimplemented!(),
+                instruction::OPCODE_BITS_IN_B => format_vi!(|reg1, reg2, disp16| {
+                    let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
+
+                    cycles += self.load_cycles(interconnect, addr, 1);
+                    is_load = true;
+                    let val = interconnect.read_byte(addr);
+                    self.set_reg_gpr(reg2, val as _);
+                }),
+                instruction::OPCODE_BITS_IN_H => format_vi!(|reg1, reg2, disp16| {
+                    let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
+                    let addr = addr & 0xfffffffe;
+
+                    cycles += self.load_cycles(interconnect, addr, 2);
+                    is_load = true;
+                    let val = interconnect.read_halfword(addr);
+                    self.set_reg_gpr(reg2, val as _);
+                }),
+                instruction::OPCODE_BITS_CAXI => format_vi!(|reg1, reg2, disp16| {
+                    let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
+                    let addr = addr & 0xfffffffc;
+
+                    cycles += 2 * interconnect.access_cycles(addr, 4);
+                    let val = interconnect.read_word(addr);
+                    let cmp = self.reg_gpr(reg2);
+                    self.sub(cmp, val);
+                    if self.reg_psw_zero {
+                        let exchange = self.reg_gpr(30);
+                        interconnect.write_word(addr, exchange);
+                    } else {
+                        interconnect.write_word(addr, val);
+                    }
+                    self.set_reg_gpr(reg2, val);
+                }),
+                instruction::OPCODE_BITS_IN_W => format_vi!(|reg1, reg2, disp16| {
+                    let addr = self.reg_gpr(reg1).wrapping_add(disp16 as u32);
+                    let addr = addr & 0xfffffffc;
+
+                    cycles += self.load_cycles(interconnect, addr, 4);
+                    is_load = true;
+                    let val = interconnect.read_word(addr);
+                    self.set_reg_gpr(reg2, val);
+                }),
+                instruction::OPCODE_BITS_FP => format_vii!(|subop, reg1, reg2| {
+                    cycles = subop_cycles(subop);
+                    let res = match subop {
+                        instruction::OPCODE_SUBOP_CMPF_S => {
+                            let lhs = self.reg_gpr(reg2);
+                            let rhs = self.reg_gpr(reg1);
+                            self.float_compare(lhs, rhs)
+                        }
+                        instruction::OPCODE_SUBOP_CVT_WS => {
+                            let val = self.reg_gpr(reg1) as i32;
+                            let res = val as f32;
+                            if (res as f64) != (val as f64) {
+                                self.reg_psw_fp_precision_degredation = true;
+                            }
+                            self.set_float_flags(res);
+                            self.set_reg_gpr(reg2, res.to_bits());
+                            Ok(())
+                        }
+                        instruction::OPCODE_SUBOP_CVT_SW | instruction::OPCODE_SUBOP_TRNC_SW => {
+                            let val = self.reg_gpr(reg1);
+                            let truncate = subop == instruction::OPCODE_SUBOP_TRNC_SW;
+                            self.float_to_int(val, truncate).map(|res| {
+                                self.reg_psw_overflow = false;
+                                self.set_zero_sign_flags(res);
+                                self.set_reg_gpr(reg2, res);
+                            })
+                        }
+                        instruction::OPCODE_SUBOP_ADDF_S |
+                        instruction::OPCODE_SUBOP_SUBF_S |
+                        instruction::OPCODE_SUBOP_MULF_S |
+                        instruction::OPCODE_SUBOP_DIVF_S => {
+                            let lhs = self.reg_gpr(reg2);
+                            let rhs = self.reg_gpr(reg1);
+                            self.float_arith(subop, lhs, rhs).map(|res| {
+                                self.set_reg_gpr(reg2, res.to_bits());
+                            })
+                        }
+                        instruction::OPCODE_SUBOP_XB => {
+                            let val = self.reg_gpr(reg2);
+                            let res = (val & 0xffff0000) | ((val & 0x000000ff) << 8) | ((val >> 8) & 0x000000ff);
+                            self.set_reg_gpr(reg2, res);
+                            Ok(())
+                        }
+                        instruction::OPCODE_SUBOP_XH => {
+                            let val = self.reg_gpr(reg2);
+                            self.set_reg_gpr(reg2, val.rotate_left(16));
+                            Ok(())
+                        }
+                        instruction::OPCODE_SUBOP_REV => {
+                            let val = self.reg_gpr(reg1);
+                            self.set_reg_gpr(reg2, val.reverse_bits());
+                            Ok(())
+                        }
+                        instruction::OPCODE_SUBOP_MPYHW => {
+                            // Only the low 17 bits of reg1 take part, as a signed value
+                            let lhs = self.reg_gpr(reg2) as i32;
+                            let rhs = ((self.reg_gpr(reg1) as i32) << 15) >> 15;
+                            self.set_reg_gpr(reg2, lhs.wrapping_mul(rhs) as u32);
+                            Ok(())
+                        }
+                        _ => Err(EXCEPTION_ILLEGAL_OPCODE),
+                    };
+
+                    if let Err(code) = res {
+                        next_pc = self.raise_exception(interconnect, code, self.reg_pc);
+                    }
+                }),
                 _ => {
-                    let second_halfword = interconnect.read_halfword(next_pc);
-                    let instr = instruction::from_halfwords(first_halfword, second_halfword);
-                    panic!("Unimplemented instruction {}", instr);
+                    next_pc = self.raise_exception(interconnect, EXCEPTION_ILLEGAL_OPCODE, self.reg_pc);
                 }
             }
         }
 
+        // The instruction at ADTRE is allowed to complete before the trap is taken, unless it raised an
+        // exception of its own (which clears AE)
+        if address_trap && self.reg_psw_address_trap_enable {
+            next_pc = self.raise_exception(interconnect, EXCEPTION_ADDRESS_TRAP, next_pc);
+        }
+
         self.reg_pc = next_pc;
+        self.last_was_load = is_load;
+
+        cycles + fetch_cycles
+    }
+
+    pub fn request_interrupt(&mut self, interrupt_code: u16) {
+        let level = ((interrupt_code >> 4) & 0x0f) as u8;
+
+        if self.reg_psw_nmi_pending || self.reg_psw_exception_pending || self.reg_psw_interrupt_disable {
+            return;
+        }
+        if level < self.reg_psw_interrupt_mask_level {
+            return;
+        }
+
+        // The PC has already moved past a HALT, so returning from the interrupt resumes after it
+        self.halted = false;
 
-        cycles
+        self.reg_eipc = self.reg_pc;
+        self.reg_eipsw = self.reg_psw();
+        let fecc = (self.reg_ecr >> 16) as u16;
+        self.set_ecr(fecc, EXCEPTION_INTERRUPT_BASE | ((level as u16) << 4));
+        self.reg_psw_exception_pending = true;
+        self.reg_psw_interrupt_disable = true;
+        self.reg_psw_address_trap_enable = false;
+        self.reg_psw_interrupt_mask_level = if level < 15 { level + 1 } else { level };
+
+        self.reg_pc = exception_handler_address(EXCEPTION_INTERRUPT_BASE | ((level as u16) << 4));
     }
 
-    pub fn request_interrupt(&mut self, _interrupt_code: u16) {
-        unimplemented!();
+    fn raise_exception(&mut self, interconnect: &mut Interconnect, code: u16, return_pc: u32) -> u32 {
+        if self.reg_psw_nmi_pending {
+            // Fatal exception: dump the exception state to the start of memory and stop the processor
+            let psw = self.reg_psw();
+            interconnect.write_word(0x00000000, 0xffff0000 | (code as u32));
+            interconnect.write_word(0x00000004, psw);
+            interconnect.write_word(0x00000008, return_pc);
+            self.halted = true;
+            return return_pc;
+        }
+
+        if self.reg_psw_exception_pending {
+            // Duplexed exception
+            self.reg_fepc = return_pc;
+            self.reg_fepsw = self.reg_psw();
+            let eicc = self.reg_ecr as u16;
+            self.set_ecr(code, eicc);
+            self.reg_psw_nmi_pending = true;
+            self.reg_psw_interrupt_disable = true;
+            self.reg_psw_address_trap_enable = false;
+
+            return exception_handler_address(EXCEPTION_DUPLEXED);
+        }
+
+        self.reg_eipc = return_pc;
+        self.reg_eipsw = self.reg_psw();
+        let fecc = (self.reg_ecr >> 16) as u16;
+        self.set_ecr(fecc, code);
+        self.reg_psw_exception_pending = true;
+        self.reg_psw_interrupt_disable = true;
+        self.reg_psw_address_trap_enable = false;
+
+        exception_handler_address(code)
     }
 
     pub fn reg_pc(&self) -> u32 {
@@ -368,11 +709,31 @@ impl V810 {
         self.reg_eipsw
     }
 
+    pub fn reg_fepc(&self) -> u32 {
+        self.reg_fepc
+    }
+
+    pub fn reg_fepsw(&self) -> u32 {
+        self.reg_fepsw
+    }
+
+    pub fn reg_ecr(&self) -> u32 {
+        self.reg_ecr
+    }
+
+    pub fn reg_chcw(&self) -> u32 {
+        self.reg_chcw
+    }
+
+    pub fn reg_adtre(&self) -> u32 {
+        self.reg_adtre
+    }
+
     pub fn reg_gpr(&self, index: u16) -> u32 {
         self.reg_gpr[index as usize]
     }
 
-    fn reg_psw(&self) -> u32 {
+    pub fn reg_psw(&self) -> u32 {
         let mut val = 0;
         val |= if self.reg_psw_zero { 1 << 0 } else { 0 };
         val |= if self.reg_psw_sign { 1 << 1 } else { 0 };
@@ -402,9 +763,33 @@ impl V810 {
         self.reg_ecr = ((fecc as u32) << 16) | eicc as u32;
     }
 
-    fn set_reg_chcw(&mut self, val: u32) {
-        // FIXME
-        println!("WARN: Cache Control Word not implemented");
+    fn set_reg_chcw(&mut self, interconnect: &mut Interconnect, val: u32) {
+        // Only ICE reads back, the remaining bits are commands to the cache
+        self.reg_chcw = val & 0x00000002;
+        self.cache.set_enabled((val & 0x00000002) != 0);
+
+        if (val & 0x00000001) != 0 {
+            // CEN is the first entry to clear and CEC how many to clear
+            let start = ((val >> 20) & 0x0fff) as usize;
+            let count = ((val >> 8) & 0x0fff) as usize;
+            self.cache.clear(start, count);
+        }
+        if (val & 0x00000010) != 0 {
+            self.cache.dump(interconnect, val & 0xffffff00);
+        } else if (val & 0x00000020) != 0 {
+            self.cache.restore(interconnect, val & 0xffffff00);
+        }
+    }
+
+    fn fetch_halfword(&mut self, interconnect: &mut Interconnect, addr: u32, fetch_cycles: &mut usize) -> u16 {
+        let (halfword, hit) = self.cache.read_halfword(interconnect, addr);
+        if !hit {
+            // Base timings assume a zero wait state fetch, so only bus wait states are added here. A cache fill
+            // reads the whole sub-block containing addr.
+            let size = if self.cache.is_enabled() { 4 } else { 2 };
+            *fetch_cycles += interconnect.access_cycles(addr, size);
+        }
+        halfword
     }
 
     #[allow(clippy::identity_op)]
@@ -478,12 +863,776 @@ impl V810 {
         }
     }
 
+    fn sar(&mut self, val: u32, shift: u32) -> u32 {
+        let shift = shift & 0x0000001f;
+        if shift == 0 {
+            self.reg_psw_carry = false;
+            self.reg_psw_overflow = false;
+            self.set_zero_sign_flags(val);
+            val
+        } else {
+            let res = (val as i32) >> (shift - 1);
+            self.reg_psw_carry = (res & 0x00000001) != 0;
+            let res = (res >> 1) as u32;
+            self.reg_psw_overflow = false;
+            self.set_zero_sign_flags(res);
+            res
+        }
+    }
+
     fn set_zero_sign_flags(&mut self, val: u32) {
         self.reg_psw_zero = val == 0;
         self.reg_psw_sign = (val & 0x80000000) != 0;
     }
+
+    fn float_arith(&mut self, subop: u16, lhs: u32, rhs: u32) -> Result<f32, u16> {
+        let lhs = f32::from_bits(lhs);
+        let rhs = f32::from_bits(rhs);
+        if is_reserved_operand(lhs) || is_reserved_operand(rhs) {
+            self.reg_psw_fp_reserved_operand = true;
+            return Err(EXCEPTION_FP_RESERVED_OPERAND);
+        }
+
+        let (mut res, exact) = match subop {
+            instruction::OPCODE_SUBOP_SUBF_S => two_sum(lhs, -rhs),
+            instruction::OPCODE_SUBOP_ADDF_S => two_sum(lhs, rhs),
+            instruction::OPCODE_SUBOP_MULF_S => {
+                // The product of two singles always fits in a double, so this rounds only once
+                let product = (lhs as f64) * (rhs as f64);
+                let res = product as f32;
+                (res, (res as f64) == product)
+            }
+            instruction::OPCODE_SUBOP_DIVF_S => {
+                if rhs == 0.0 {
+                    if lhs == 0.0 {
+                        self.reg_psw_fp_invalid_operation = true;
+                        return Err(EXCEPTION_FP_INVALID_OPERATION);
+                    }
+                    self.reg_psw_fp_zero_division = true;
+                    return Err(EXCEPTION_FP_ZERO_DIVISION);
+                }
+                let res = lhs / rhs;
+                (res, (res as f64) * (rhs as f64) == (lhs as f64))
+            }
+            _ => unreachable!(),
+        };
+
+        if res.is_infinite() {
+            self.reg_psw_fp_overflow = true;
+            self.reg_psw_fp_precision_degredation = true;
+            return Err(EXCEPTION_FP_OVERFLOW);
+        }
+        if (res == 0.0 && !exact) || res.is_subnormal() {
+            self.reg_psw_fp_underflow = true;
+            self.reg_psw_fp_precision_degredation = true;
+            res = 0.0;
+        } else if !exact {
+            self.reg_psw_fp_precision_degredation = true;
+        }
+
+        self.set_float_flags(res);
+        Ok(res)
+    }
+
+    // Unlike SUBF.S a compare can't overflow or lose precision, so only reserved operands raise an exception
+    fn float_compare(&mut self, lhs: u32, rhs: u32) -> Result<(), u16> {
+        let lhs = f32::from_bits(lhs);
+        let rhs = f32::from_bits(rhs);
+        if is_reserved_operand(lhs) || is_reserved_operand(rhs) {
+            self.reg_psw_fp_reserved_operand = true;
+            return Err(EXCEPTION_FP_RESERVED_OPERAND);
+        }
+
+        self.reg_psw_zero = lhs == rhs;
+        self.reg_psw_sign = lhs < rhs;
+        self.reg_psw_carry = lhs < rhs;
+        self.reg_psw_overflow = false;
+        Ok(())
+    }
+
+    fn float_to_int(&mut self, val: u32, truncate: bool) -> Result<u32, u16> {
+        let val = f32::from_bits(val);
+        if is_reserved_operand(val) {
+            self.reg_psw_fp_reserved_operand = true;
+            return Err(EXCEPTION_FP_RESERVED_OPERAND);
+        }
+
+        let res = if truncate { val.trunc() } else { val.round_ties_even() };
+        if !(-2147483648.0..2147483648.0).contains(&res) {
+            self.reg_psw_fp_invalid_operation = true;
+            return Err(EXCEPTION_FP_INVALID_OPERATION);
+        }
+        if res != val {
+            self.reg_psw_fp_precision_degredation = true;
+        }
+
+        Ok(res as i32 as u32)
+    }
+
+    fn bit_string_search(&mut self, interconnect: &mut Interconnect, want: u32, upward: bool) -> bool {
+        let mut src_addr = self.reg_gpr(30) & 0xfffffffc;
+        let mut src_offset = self.reg_gpr(27) & 0x1f;
+        let mut len = self.reg_gpr(28);
+        let mut skipped = self.reg_gpr(29);
+
+        let mut found = false;
+        if len != 0 {
+            let src = interconnect.read_word(src_addr);
+            loop {
+                found = ((src >> src_offset) & 0x01) == want;
+                len -= 1;
+                skipped = skipped.wrapping_add(1);
+
+                let word_crossed = if upward {
+                    src_offset = (src_offset + 1) & 0x1f;
+                    src_offset == 0
+                } else {
+                    src_offset = src_offset.wrapping_sub(1) & 0x1f;
+                    src_offset == 0x1f
+                };
+                if word_crossed {
+                    src_addr = if upward { src_addr.wrapping_add(4) } else { src_addr.wrapping_sub(4) };
+                }
+
+                if found || word_crossed || len == 0 {
+                    break;
+                }
+            }
+        }
+
+        self.set_reg_gpr(27, src_offset);
+        self.set_reg_gpr(28, len);
+        self.set_reg_gpr(29, skipped);
+        self.set_reg_gpr(30, src_addr);
+        self.reg_psw_zero = !found;
+
+        found || len == 0
+    }
+
+    fn bit_string_transfer(&mut self, interconnect: &mut Interconnect, subop: u16) -> bool {
+        let mut dst_offset = self.reg_gpr(26) & 0x1f;
+        let mut src_offset = self.reg_gpr(27) & 0x1f;
+        let mut len = self.reg_gpr(28);
+        let mut dst_addr = self.reg_gpr(29) & 0xfffffffc;
+        let mut src_addr = self.reg_gpr(30) & 0xfffffffc;
+
+        if len != 0 {
+            let mut src = interconnect.read_word(src_addr);
+            let mut dst = interconnect.read_word(dst_addr);
+            loop {
+                let src_bit = (src >> src_offset) & 0x01;
+                let dst_bit = (dst >> dst_offset) & 0x01;
+                let res = match subop {
+                    instruction::OPCODE_SUBOP_ORBSU => dst_bit | src_bit,
+                    instruction::OPCODE_SUBOP_ANDBSU => dst_bit & src_bit,
+                    instruction::OPCODE_SUBOP_XORBSU => dst_bit ^ src_bit,
+                    instruction::OPCODE_SUBOP_MOVBSU => src_bit,
+                    instruction::OPCODE_SUBOP_ORNBSU => dst_bit | (src_bit ^ 0x01),
+                    instruction::OPCODE_SUBOP_ANDNBSU => dst_bit & (src_bit ^ 0x01),
+                    instruction::OPCODE_SUBOP_XORNBSU => dst_bit ^ (src_bit ^ 0x01),
+                    instruction::OPCODE_SUBOP_NOTBSU => src_bit ^ 0x01,
+                    _ => unreachable!(),
+                };
+                dst = (dst & !(1 << dst_offset)) | (res << dst_offset);
+                len -= 1;
+
+                src_offset = (src_offset + 1) & 0x1f;
+                dst_offset = (dst_offset + 1) & 0x1f;
+                if src_offset == 0 {
+                    src_addr = src_addr.wrapping_add(4);
+                    if len != 0 && dst_offset != 0 {
+                        src = interconnect.read_word(src_addr);
+                    }
+                }
+
+                if dst_offset == 0 || len == 0 {
+                    break;
+                }
+            }
+
+            interconnect.write_word(dst_addr, dst);
+            if dst_offset == 0 {
+                dst_addr = dst_addr.wrapping_add(4);
+            }
+        }
+
+        self.set_reg_gpr(26, dst_offset);
+        self.set_reg_gpr(27, src_offset);
+        self.set_reg_gpr(28, len);
+        self.set_reg_gpr(29, dst_addr);
+        self.set_reg_gpr(30, src_addr);
+
+        len == 0
+    }
+
+    fn load_cycles(&self, interconnect: &Interconnect, addr: u32, size: u32) -> usize {
+        // A load straight after another load overlaps its address calculation with the previous bus access
+        let setup_cycles = if self.last_was_load { 0 } else { 1 };
+        setup_cycles + interconnect.access_cycles(addr, size)
+    }
+
+    fn set_float_flags(&mut self, val: f32) {
+        self.reg_psw_zero = val == 0.0;
+        self.reg_psw_sign = val < 0.0;
+        self.reg_psw_carry = val < 0.0;
+        self.reg_psw_overflow = false;
+    }
+
+    fn condition(&self, cond: u16) -> bool {
+        match cond {
+            0x0 => self.reg_psw_overflow, // V
+            0x1 => self.reg_psw_carry, // C/L
+            0x2 => self.reg_psw_zero, // Z/E
+            0x3 => self.reg_psw_carry || self.reg_psw_zero, // NH
+            0x4 => self.reg_psw_sign, // N
+            0x5 => true, // T
+            0x6 => self.reg_psw_sign ^ self.reg_psw_overflow, // LT
+            0x7 => (self.reg_psw_sign ^ self.reg_psw_overflow) || self.reg_psw_zero, // LE
+            0x8 => !self.reg_psw_overflow, // NV
+            0x9 => !self.reg_psw_carry, // NC/NL
+            0xa => !self.reg_psw_zero, // NZ/NE
+            0xb => !(self.reg_psw_carry || self.reg_psw_zero), // H
+            0xc => !self.reg_psw_sign, // P
+            0xd => false, // F
+            0xe => !(self.reg_psw_sign ^ self.reg_psw_overflow), // GE
+            0xf => !((self.reg_psw_sign ^ self.reg_psw_overflow) || self.reg_psw_zero), // GT
+            _ => unreachable!(),
+        }
+    }
 }
 
 fn sign_extend_imm5(imm5: u16) -> u32 {
     (((imm5 as i32) << 27) >> 27) as _
-}
\ No newline at end of file
+}
+
+// Base cycle counts, assuming no bus wait states
+fn opcode_cycles(opbits: u16) -> usize {
+    match opbits {
+        instruction::OPCODE_BITS_JMP |
+        instruction::OPCODE_BITS_JR |
+        instruction::OPCODE_BITS_JAL => 3,
+        instruction::OPCODE_BITS_MUL |
+        instruction::OPCODE_BITS_MULU => 13,
+        instruction::OPCODE_BITS_DIV => 38,
+        instruction::OPCODE_BITS_DIVU => 36,
+        instruction::OPCODE_BITS_CLI |
+        instruction::OPCODE_BITS_SEI => 12,
+        instruction::OPCODE_BITS_TRAP => 15,
+        instruction::OPCODE_BITS_RETI => 10,
+        instruction::OPCODE_BITS_LDSR |
+        instruction::OPCODE_BITS_STSR => 8,
+        instruction::OPCODE_BITS_LD_B |
+        instruction::OPCODE_BITS_LD_H |
+        instruction::OPCODE_BITS_LD_W |
+        instruction::OPCODE_BITS_IN_B |
+        instruction::OPCODE_BITS_IN_H |
+        instruction::OPCODE_BITS_IN_W => 4,
+        instruction::OPCODE_BITS_ST_B |
+        instruction::OPCODE_BITS_ST_H |
+        instruction::OPCODE_BITS_ST_W |
+        instruction::OPCODE_BITS_OUT_B |
+        instruction::OPCODE_BITS_OUT_H |
+        instruction::OPCODE_BITS_OUT_W => 4,
+        instruction::OPCODE_BITS_CAXI => 26,
+        _ => 1,
+    }
+}
+
+// Worst case cycle counts for the Format VII sub-opcodes
+fn subop_cycles(subop: u16) -> usize {
+    match subop {
+        instruction::OPCODE_SUBOP_CMPF_S => 10,
+        instruction::OPCODE_SUBOP_CVT_WS => 16,
+        instruction::OPCODE_SUBOP_CVT_SW => 14,
+        instruction::OPCODE_SUBOP_ADDF_S => 28,
+        instruction::OPCODE_SUBOP_SUBF_S => 28,
+        instruction::OPCODE_SUBOP_MULF_S => 30,
+        instruction::OPCODE_SUBOP_DIVF_S => 44,
+        instruction::OPCODE_SUBOP_XB => 6,
+        instruction::OPCODE_SUBOP_XH => 1,
+        instruction::OPCODE_SUBOP_REV => 22,
+        instruction::OPCODE_SUBOP_TRNC_SW => 14,
+        instruction::OPCODE_SUBOP_MPYHW => 9,
+        _ => 1,
+    }
+}
+
+fn exception_handler_address(code: u16) -> u32 {
+    match code {
+        // All floating-point exceptions share a single handler
+        0xff60..=0xff7f => 0xffffff60,
+        _ => 0xffff0000 | ((code as u32) & 0xfff0),
+    }
+}
+
+// NaNs, infinities and denormals are all reserved operands on the V810
+fn is_reserved_operand(val: f32) -> bool {
+    !val.is_finite() || val.is_subnormal()
+}
+
+// Returns the rounded sum and whether it represents the exact sum of the operands
+fn two_sum(lhs: f32, rhs: f32) -> (f32, bool) {
+    let sum = lhs + rhs;
+    let rhs_virtual = sum - lhs;
+    let err = (lhs - (sum - rhs_virtual)) + (rhs - rhs_virtual);
+    (sum, err == 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::NUM_CACHE_ENTRIES;
+    use crate::rom::{Rom, MIN_ROM_SIZE};
+
+    const PROGRAM_ADDR: u32 = 0x05000000;
+    const DATA_ADDR: u32 = 0x05008000;
+
+    fn setup() -> (V810, Interconnect) {
+        let rom = Rom::from_bytes(&[0; MIN_ROM_SIZE]).unwrap();
+        let mut cpu = V810::new();
+        cpu.reset();
+        // Clear NP so exceptions are taken normally rather than being fatal
+        cpu.set_reg_psw(0);
+        cpu.reg_pc = PROGRAM_ADDR;
+        (cpu, Interconnect::new(rom))
+    }
+
+    fn format_i(opbits: u16, reg1: u16, reg2: u16) -> u16 {
+        (opbits << 10) | (reg2 << 5) | reg1
+    }
+
+    fn format_vii(subop: u16, reg1: u16, reg2: u16) -> [u16; 2] {
+        [format_i(instruction::OPCODE_BITS_FP, reg1, reg2), subop << 10]
+    }
+
+    // Runs a floating-point instruction on reg2 = lhs and reg1 = rhs, returning the result left in reg2
+    fn float_op(cpu: &mut V810, interconnect: &mut Interconnect, subop: u16, lhs: f32, rhs: f32) -> f32 {
+        cpu.reg_pc = PROGRAM_ADDR;
+        cpu.set_reg_gpr(1, rhs.to_bits());
+        cpu.set_reg_gpr(2, lhs.to_bits());
+        execute(cpu, interconnect, &format_vii(subop, 1, 2));
+        f32::from_bits(cpu.reg_gpr(2))
+    }
+
+    // Writes the instruction out at the PC and executes it
+    fn execute(cpu: &mut V810, interconnect: &mut Interconnect, halfwords: &[u16]) {
+        for (i, &halfword) in halfwords.iter().enumerate() {
+            interconnect.write_halfword(cpu.reg_pc + 2 * i as u32, halfword);
+        }
+        cpu.step(interconnect);
+    }
+
+    #[test]
+    fn div_overflow() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_gpr(1, 0xffffffff);
+        cpu.set_reg_gpr(2, 0x80000000);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_DIV, 1, 2)]);
+
+        assert_eq!(cpu.reg_gpr(2), 0x80000000);
+        assert_eq!(cpu.reg_gpr(30), 0);
+        assert!(cpu.reg_psw_overflow);
+        assert!(cpu.reg_psw_sign);
+        assert!(!cpu.reg_psw_zero);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 2);
+    }
+
+    #[test]
+    fn caxi_exchange() {
+        let (mut cpu, mut interconnect) = setup();
+        interconnect.write_word(DATA_ADDR, 0x12345678);
+        cpu.set_reg_gpr(1, DATA_ADDR);
+        cpu.set_reg_gpr(2, 0x12345678);
+        cpu.set_reg_gpr(30, 0xcafef00d);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_CAXI, 1, 2), 0x0000]);
+
+        assert_eq!(interconnect.read_word(DATA_ADDR), 0xcafef00d);
+        assert_eq!(cpu.reg_gpr(2), 0x12345678);
+        assert!(cpu.reg_psw_zero);
+        assert!(!cpu.reg_psw_carry);
+    }
+
+    #[test]
+    fn caxi_no_exchange() {
+        let (mut cpu, mut interconnect) = setup();
+        interconnect.write_word(DATA_ADDR + 4, 0x12345678);
+        cpu.set_reg_gpr(1, DATA_ADDR);
+        cpu.set_reg_gpr(2, 0x00000001);
+        cpu.set_reg_gpr(30, 0xcafef00d);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_CAXI, 1, 2), 0x0004]);
+
+        assert_eq!(interconnect.read_word(DATA_ADDR + 4), 0x12345678);
+        assert_eq!(cpu.reg_gpr(2), 0x12345678);
+        assert!(!cpu.reg_psw_zero);
+        assert!(cpu.reg_psw_carry);
+    }
+
+    #[test]
+    fn sar_carry() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_gpr(2, 0x80000003);
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_SAR_I, 1, 2)]);
+        assert_eq!(cpu.reg_gpr(2), 0xc0000001);
+        assert!(cpu.reg_psw_carry);
+        assert!(cpu.reg_psw_sign);
+
+        cpu.set_reg_gpr(2, 0x80000001);
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_SAR_I, 2, 2)]);
+        assert_eq!(cpu.reg_gpr(2), 0xe0000000);
+        assert!(!cpu.reg_psw_carry);
+
+        // Shifting by zero clears carry
+        cpu.reg_psw_carry = true;
+        cpu.set_reg_gpr(1, 0x00000020);
+        cpu.set_reg_gpr(2, 0x00000001);
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_SAR_R, 1, 2)]);
+        assert_eq!(cpu.reg_gpr(2), 0x00000001);
+        assert!(!cpu.reg_psw_carry);
+        assert!(!cpu.reg_psw_zero);
+    }
+
+    #[test]
+    fn float_add_sub() {
+        let (mut cpu, mut interconnect) = setup();
+
+        assert_eq!(float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_ADDF_S, 1.5, 2.25), 3.75);
+        assert!(!cpu.reg_psw_fp_precision_degredation);
+        assert!(!cpu.reg_psw_zero && !cpu.reg_psw_sign && !cpu.reg_psw_carry);
+
+        assert_eq!(float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SUBF_S, 1.5, 2.25), -0.75);
+        assert!(cpu.reg_psw_sign && cpu.reg_psw_carry);
+
+        assert_eq!(float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SUBF_S, 2.25, 2.25), 0.0);
+        assert!(cpu.reg_psw_zero);
+        assert!(!cpu.reg_psw_fp_precision_degredation);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 4);
+    }
+
+    #[test]
+    fn float_add_rounds_once() {
+        let (mut cpu, mut interconnect) = setup();
+
+        // Halfway between 1.0 and the next single, plus a little more: rounds up rather than to even
+        let rhs = f32::from_bits(0x33800001);
+        let res = float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_ADDF_S, 1.0, rhs);
+        assert_eq!(res, 1.0 + f32::EPSILON);
+        assert!(cpu.reg_psw_fp_precision_degredation);
+
+        // Far below half an ulp, rounds back to the larger operand
+        cpu.reg_psw_fp_precision_degredation = false;
+        let res = float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SUBF_S, 1.0, 1.0e-30);
+        assert_eq!(res, 1.0);
+        assert!(cpu.reg_psw_fp_precision_degredation);
+    }
+
+    #[test]
+    fn float_compare() {
+        let (mut cpu, mut interconnect) = setup();
+        let res = float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, 1.0, 2.0);
+        assert_eq!(res, 1.0);
+        assert!(cpu.reg_psw_sign && cpu.reg_psw_carry && !cpu.reg_psw_zero);
+
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, -3.0, -3.0);
+        assert!(cpu.reg_psw_zero && !cpu.reg_psw_sign && !cpu.reg_psw_carry);
+    }
+
+    #[test]
+    fn float_compare_never_overflows() {
+        let (mut cpu, mut interconnect) = setup();
+
+        // As a subtraction these would overflow or lose precision, but a compare only sets the condition flags
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, f32::MAX, -f32::MAX);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 4);
+        assert!(!cpu.reg_psw_zero && !cpu.reg_psw_sign && !cpu.reg_psw_carry && !cpu.reg_psw_overflow);
+
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, -f32::MAX, f32::MAX);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 4);
+        assert!(cpu.reg_psw_sign && cpu.reg_psw_carry);
+
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, 1.0, 1.0e-30);
+        assert!(!cpu.reg_psw_sign);
+        assert!(!cpu.reg_psw_fp_overflow);
+        assert!(!cpu.reg_psw_fp_underflow);
+        assert!(!cpu.reg_psw_fp_precision_degredation);
+
+        // Reserved operands are still caught
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_CMPF_S, 1.0, f32::INFINITY);
+        assert_float_exception(&cpu, EXCEPTION_FP_RESERVED_OPERAND);
+    }
+
+    #[test]
+    fn float_underflow() {
+        let (mut cpu, mut interconnect) = setup();
+        let res = float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_MULF_S, 1.0e-30, 1.0e-30);
+        assert_eq!(res, 0.0);
+        assert!(cpu.reg_psw_fp_underflow);
+        assert!(cpu.reg_psw_fp_precision_degredation);
+        assert!(cpu.reg_psw_zero);
+    }
+
+    fn assert_float_exception(cpu: &V810, code: u16) {
+        assert_eq!(cpu.reg_pc(), 0xffffff60);
+        assert_eq!(cpu.reg_ecr() & 0xffff, code as u32);
+        assert_eq!(cpu.reg_eipc(), PROGRAM_ADDR);
+        assert!(cpu.reg_psw_exception_pending);
+    }
+
+    #[test]
+    fn float_overflow() {
+        let (mut cpu, mut interconnect) = setup();
+        let res = float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_ADDF_S, f32::MAX, f32::MAX);
+        assert_float_exception(&cpu, EXCEPTION_FP_OVERFLOW);
+        assert!(cpu.reg_psw_fp_overflow);
+        assert!(cpu.reg_psw_fp_precision_degredation);
+        // The destination is left untouched
+        assert_eq!(res, f32::MAX);
+    }
+
+    #[test]
+    fn float_divide_by_zero() {
+        let (mut cpu, mut interconnect) = setup();
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_DIVF_S, 1.0, 0.0);
+        assert_float_exception(&cpu, EXCEPTION_FP_ZERO_DIVISION);
+        assert!(cpu.reg_psw_fp_zero_division);
+        assert!(!cpu.reg_psw_fp_invalid_operation);
+
+        let (mut cpu, mut interconnect) = setup();
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_DIVF_S, 0.0, 0.0);
+        assert_float_exception(&cpu, EXCEPTION_FP_INVALID_OPERATION);
+        assert!(cpu.reg_psw_fp_invalid_operation);
+        assert!(!cpu.reg_psw_fp_zero_division);
+    }
+
+    #[test]
+    fn float_reserved_operand() {
+        let (mut cpu, mut interconnect) = setup();
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_ADDF_S, f32::NAN, 1.0);
+        assert_float_exception(&cpu, EXCEPTION_FP_RESERVED_OPERAND);
+        assert!(cpu.reg_psw_fp_reserved_operand);
+
+        let (mut cpu, mut interconnect) = setup();
+        let denormal = f32::from_bits(0x00000001);
+        float_op(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_MULF_S, 1.0, denormal);
+        assert_float_exception(&cpu, EXCEPTION_FP_RESERVED_OPERAND);
+    }
+
+    fn bit_string(cpu: &mut V810, interconnect: &mut Interconnect, subop: u16) {
+        execute(cpu, interconnect, &[format_i(instruction::OPCODE_BITS_BS, subop, 0)]);
+    }
+
+    #[test]
+    fn bit_string_search_across_words() {
+        let (mut cpu, mut interconnect) = setup();
+        interconnect.write_word(DATA_ADDR, 0x00000000);
+        interconnect.write_word(DATA_ADDR + 4, 0x00000008);
+        cpu.set_reg_gpr(27, 30);
+        cpu.set_reg_gpr(28, 40);
+        cpu.set_reg_gpr(29, 0);
+        cpu.set_reg_gpr(30, DATA_ADDR);
+
+        // The first step runs off the end of the word and re-executes the instruction
+        bit_string(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SCH1BSU);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR);
+        assert_eq!(cpu.reg_gpr(27), 0);
+        assert_eq!(cpu.reg_gpr(28), 38);
+        assert_eq!(cpu.reg_gpr(29), 2);
+        assert_eq!(cpu.reg_gpr(30), DATA_ADDR + 4);
+        assert!(cpu.reg_psw_zero);
+
+        bit_string(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SCH1BSU);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 2);
+        assert_eq!(cpu.reg_gpr(27), 4);
+        assert_eq!(cpu.reg_gpr(28), 34);
+        assert_eq!(cpu.reg_gpr(29), 6);
+        assert_eq!(cpu.reg_gpr(30), DATA_ADDR + 4);
+        assert!(!cpu.reg_psw_zero);
+    }
+
+    #[test]
+    fn bit_string_search_not_found() {
+        let (mut cpu, mut interconnect) = setup();
+        interconnect.write_word(DATA_ADDR, 0xffffffff);
+        cpu.set_reg_gpr(27, 7);
+        cpu.set_reg_gpr(28, 4);
+        cpu.set_reg_gpr(29, 0);
+        cpu.set_reg_gpr(30, DATA_ADDR);
+
+        bit_string(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_SCH0BSD);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 2);
+        assert_eq!(cpu.reg_gpr(27), 3);
+        assert_eq!(cpu.reg_gpr(28), 0);
+        assert_eq!(cpu.reg_gpr(29), 4);
+        assert_eq!(cpu.reg_gpr(30), DATA_ADDR);
+        assert!(cpu.reg_psw_zero);
+    }
+
+    #[test]
+    fn bit_string_transfer_across_words() {
+        let (mut cpu, mut interconnect) = setup();
+        let src_addr = DATA_ADDR;
+        let dst_addr = DATA_ADDR + 0x100;
+        interconnect.write_word(src_addr, 0x000000a5);
+        interconnect.write_word(dst_addr, 0x0fffffff);
+        interconnect.write_word(dst_addr + 4, 0xfffffff0);
+        cpu.set_reg_gpr(26, 28);
+        cpu.set_reg_gpr(27, 0);
+        cpu.set_reg_gpr(28, 8);
+        cpu.set_reg_gpr(29, dst_addr);
+        cpu.set_reg_gpr(30, src_addr);
+
+        // The first step fills the rest of the destination word and re-executes the instruction
+        bit_string(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_MOVBSU);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR);
+        assert_eq!(interconnect.read_word(dst_addr), 0x5fffffff);
+        assert_eq!(cpu.reg_gpr(26), 0);
+        assert_eq!(cpu.reg_gpr(27), 4);
+        assert_eq!(cpu.reg_gpr(28), 4);
+        assert_eq!(cpu.reg_gpr(29), dst_addr + 4);
+        assert_eq!(cpu.reg_gpr(30), src_addr);
+
+        bit_string(&mut cpu, &mut interconnect, instruction::OPCODE_SUBOP_MOVBSU);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 2);
+        assert_eq!(interconnect.read_word(dst_addr + 4), 0xfffffffa);
+        assert_eq!(cpu.reg_gpr(26), 4);
+        assert_eq!(cpu.reg_gpr(27), 8);
+        assert_eq!(cpu.reg_gpr(28), 0);
+        assert_eq!(cpu.reg_gpr(29), dst_addr + 4);
+        assert_eq!(cpu.reg_gpr(30), src_addr);
+    }
+
+    #[test]
+    fn interrupt_masking() {
+        let (mut cpu, _) = setup();
+
+        // Disabled by ID, by a pending exception or NMI, and below the PSW's interrupt level
+        for &psw in [0x00001000, 0x00004000, 0x00008000, 0x00050000].iter() {
+            cpu.set_reg_psw(psw);
+            cpu.request_interrupt(0xfe40);
+            assert_eq!(cpu.reg_pc(), PROGRAM_ADDR, "PSW 0x{:08x}", psw);
+            assert_eq!(cpu.reg_psw(), psw);
+        }
+
+        // At or above the interrupt level it's taken
+        cpu.set_reg_psw(0x00040000);
+        cpu.request_interrupt(0xfe40);
+        assert_eq!(cpu.reg_pc(), 0xfffffe40);
+        assert_eq!(cpu.reg_eipc(), PROGRAM_ADDR);
+        assert_eq!(cpu.reg_eipsw(), 0x00040000);
+        assert_eq!(cpu.reg_ecr() & 0xffff, 0xfe40);
+        // EP and ID are set, and the interrupt level is raised above the one taken
+        assert_eq!(cpu.reg_psw(), 0x00055000);
+    }
+
+    #[test]
+    fn exception_saves_state() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_psw(0x00022009);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_TRAP, 0x13, 0)]);
+
+        assert_eq!(cpu.reg_pc(), 0xffffffb0);
+        // TRAP returns to the following instruction
+        assert_eq!(cpu.reg_eipc(), PROGRAM_ADDR + 2);
+        assert_eq!(cpu.reg_eipsw(), 0x00022009);
+        assert_eq!(cpu.reg_ecr(), 0x0000ffb3);
+        // AE is cleared, the flags and interrupt level are kept
+        assert_eq!(cpu.reg_psw(), 0x00025009);
+    }
+
+    #[test]
+    fn duplexed_exception() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_psw(0x00004000);
+        cpu.set_ecr(0x0000, 0xfe40);
+        cpu.set_reg_gpr(1, 0);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_DIV, 1, 2)]);
+
+        assert_eq!(cpu.reg_pc(), 0xffffffd0);
+        assert_eq!(cpu.reg_fepc(), PROGRAM_ADDR);
+        assert_eq!(cpu.reg_fepsw(), 0x00004000);
+        assert_eq!(cpu.reg_ecr(), 0xff80fe40);
+        assert_eq!(cpu.reg_psw(), 0x0000d000);
+    }
+
+    #[test]
+    fn fatal_exception_halts() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_psw(0x0000c000);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_TRAP, 0x02, 0)]);
+
+        assert!(cpu.halted);
+        assert_eq!(interconnect.read_word(0x00000000), 0xffffffa2);
+        assert_eq!(interconnect.read_word(0x00000004), 0x0000c000);
+        assert_eq!(interconnect.read_word(0x00000008), PROGRAM_ADDR + 2);
+
+        // Not even an interrupt gets it going again
+        let pc = cpu.reg_pc();
+        cpu.request_interrupt(0xfe40);
+        assert_eq!(cpu.step(&mut interconnect), 1);
+        assert_eq!(cpu.reg_pc(), pc);
+    }
+
+    #[test]
+    fn halt_wakes_on_interrupt() {
+        let (mut cpu, mut interconnect) = setup();
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_HALT, 0, 0)]);
+        assert!(cpu.halted);
+
+        assert_eq!(cpu.step(&mut interconnect), 1);
+        assert_eq!(cpu.reg_pc(), PROGRAM_ADDR + 2);
+
+        cpu.request_interrupt(0xfe10);
+        assert!(!cpu.halted);
+        assert_eq!(cpu.reg_pc(), 0xfffffe10);
+        assert_eq!(cpu.reg_eipc(), PROGRAM_ADDR + 2);
+    }
+
+    #[test]
+    fn divide_by_zero() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_gpr(1, 0);
+        cpu.set_reg_gpr(2, 1234);
+        cpu.set_reg_gpr(30, 5678);
+
+        execute(&mut cpu, &mut interconnect, &[format_i(instruction::OPCODE_BITS_DIVU, 1, 2)]);
+
+        assert_eq!(cpu.reg_pc(), 0xffffff80);
+        assert_eq!(cpu.reg_eipc(), PROGRAM_ADDR);
+        assert_eq!(cpu.reg_ecr() & 0xffff, 0xff80);
+        assert_eq!(cpu.reg_gpr(2), 1234);
+        assert_eq!(cpu.reg_gpr(30), 5678);
+    }
+
+    #[test]
+    fn chcw_clears_entries() {
+        let (mut cpu, mut interconnect) = setup();
+        cpu.set_reg_chcw(&mut interconnect, 0x00000002);
+        let fill = |cpu: &mut V810, interconnect: &mut Interconnect| {
+            for entry in 0..NUM_CACHE_ENTRIES as u32 {
+                cpu.cache.read_halfword(interconnect, PROGRAM_ADDR + entry * 8);
+            }
+        };
+
+        // CEN = 4, CEC = 3
+        fill(&mut cpu, &mut interconnect);
+        cpu.set_reg_chcw(&mut interconnect, 0x00400303);
+        for entry in 0..NUM_CACHE_ENTRIES as u32 {
+            let (_, hit) = cpu.cache.read_halfword(&mut interconnect, PROGRAM_ADDR + entry * 8);
+            assert_eq!(hit, !(4..7).contains(&entry), "entry {}", entry);
+        }
+
+        // CEN = 0, CEC = 128 clears the whole cache
+        fill(&mut cpu, &mut interconnect);
+        cpu.set_reg_chcw(&mut interconnect, 0x00008003);
+        for entry in 0..NUM_CACHE_ENTRIES as u32 {
+            let (_, hit) = cpu.cache.read_halfword(&mut interconnect, PROGRAM_ADDR + entry * 8);
+            assert!(!hit, "entry {}", entry);
+        }
+        assert_eq!(cpu.reg_chcw(), 0x00000002);
+    }
+}
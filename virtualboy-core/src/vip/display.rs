@@ -0,0 +1,109 @@
+use super::{Vip, LEFT_DISPLAY_START, LEFT_DISPLAY_END, RIGHT_DISPLAY_START, RIGHT_DISPLAY_END};
+use super::world::{Eye, DISPLAY_WIDTH, DISPLAY_HEIGHT, FRAME_BUFFER_LEFT, FRAME_BUFFER_RIGHT};
+
+pub const FRAME_WIDTH: usize = DISPLAY_WIDTH as usize;
+pub const FRAME_HEIGHT: usize = DISPLAY_HEIGHT as usize;
+
+const COLUMN_TABLE_LEFT: u32 = 0x0003dc00;
+const COLUMN_TABLE_RIGHT: u32 = 0x0003de00;
+const COLUMN_TABLE_LAST_ENTRY: u32 = 0xff;
+
+impl Vip {
+    pub fn frame(&self, eye: Eye) -> &[u8] {
+        match eye {
+            Eye::Left => &self.left_frame,
+            Eye::Right => &self.right_frame,
+        }
+    }
+
+    // Scans the displayed frame buffer for an eye out through the LEDs, converting each pixel into an intensity
+    pub(super) fn display_eye(&mut self, eye: Eye) {
+        let buffer = self.draw_buffer ^ 1;
+        let (frame_buffer, column_table) = match eye {
+            Eye::Left => (FRAME_BUFFER_LEFT[buffer], COLUMN_TABLE_LEFT),
+            Eye::Right => (FRAME_BUFFER_RIGHT[buffer], COLUMN_TABLE_RIGHT),
+        };
+
+        // Taken out of self while it is filled in so the scan can read VRAM, and put back afterwards
+        let mut frame = std::mem::take(match eye {
+            Eye::Left => &mut self.left_frame,
+            Eye::Right => &mut self.right_frame,
+        });
+        if self.reg_display_enable {
+            for x in 0..DISPLAY_WIDTH {
+                // The column table is read from its last entry backwards, each entry covering two columns
+                let entry = COLUMN_TABLE_LAST_ENTRY - (x as u32 / 2);
+                let entry = self.vram.read_halfword(column_table + entry * 2);
+                let repeat = ((entry >> 8) & 0x0f) as u32;
+                let column_time = (entry & 0xff) as u32;
+                let brightness = self.brightness(repeat, column_time);
+
+                for y in 0..DISPLAY_HEIGHT {
+                    let pixel = self.read_pixel(frame_buffer, x, y);
+                    frame[y as usize * FRAME_WIDTH + x as usize] = brightness[pixel as usize];
+                }
+            }
+        } else {
+            for val in frame.iter_mut() {
+                *val = 0;
+            }
+        }
+
+        match eye {
+            Eye::Left => self.left_frame = frame,
+            Eye::Right => self.right_frame = frame,
+        }
+    }
+
+    // CTA_L and CTA_R, the column table entries currently being read for each eye. Each count down from the last
+    // entry as their eye's frame buffer is scanned out and are left at the final entry used until the next frame.
+    pub(super) fn reg_cta(&self) -> u16 {
+        let cta_l = self.column_table_position(LEFT_DISPLAY_START, LEFT_DISPLAY_END);
+        let cta_r = self.column_table_position(RIGHT_DISPLAY_START, RIGHT_DISPLAY_END);
+        (cta_r << 8) | cta_l
+    }
+
+    fn column_table_position(&self, start: usize, end: usize) -> u16 {
+        if !self.reg_display_enable || self.frame_clock < start {
+            return COLUMN_TABLE_LAST_ENTRY as u16;
+        }
+
+        let column = (self.frame_clock.min(end - 1) - start) * FRAME_WIDTH / (end - start);
+        (COLUMN_TABLE_LAST_ENTRY - (column as u32 / 2)) as u16
+    }
+
+    // Works out how bright each of the four pixel values appears. Every repetition the LEDs are pulsed for BRTA,
+    // BRTB and BRTC in turn and then rest for REST, with colour 3 lit throughout all three pulses. The column table
+    // gives how long the mirror stays on the column, cutting short any pulses that would run past it.
+    fn brightness(&self, repeat: u32, column_time: u32) -> [u8; 4] {
+        let brta = self.reg_brta as u32;
+        let brtb = self.reg_brtb as u32;
+        let brtc = self.reg_brtc as u32;
+        let rest = self.reg_rest as u32;
+        let period = brta + 1 + brtb + 1 + brtc + 1 + rest + 1;
+
+        // Time spent lit between start and start + length, cut short at the end of the column
+        let lit = |start: u32, length: u32| (start + length).min(column_time) - start.min(column_time);
+
+        let mut levels = [0u32; 4];
+        for i in 0..=repeat {
+            let start = i * period;
+            if start >= column_time {
+                break;
+            }
+
+            let a = lit(start, brta);
+            let b = lit(start + brta + 1, brtb);
+            let c = lit(start + brta + 1 + brtb + 1, brtc);
+            levels[1] += a;
+            levels[2] += b;
+            levels[3] += a + b + c;
+        }
+
+        let mut brightness = [0; 4];
+        for (level, val) in levels.iter().zip(brightness.iter_mut()) {
+            *val = (*level).min(255) as u8;
+        }
+        brightness
+    }
+}
@@ -0,0 +1,593 @@
+use super::bus::BusDevice;
+use super::ram::Ram;
+
+mod display;
+mod world;
+
+pub use self::display::{FRAME_WIDTH, FRAME_HEIGHT};
+pub use self::world::Eye;
+
+use self::world::NUM_DRAWING_BLOCKS;
+
+// VRAM covers the frame buffers, CHR RAM, BG maps, parameter tables, world attributes, column table and OAM
+const VRAM_START: u32 = 0x00000000;
+const VRAM_END: u32 = 0x0003ffff;
+const VRAM_SIZE: u32 = 0x00040000;
+
+const IO_START: u32 = 0x0005f800;
+const IO_END: u32 = 0x0005f87f;
+
+// Linear view of all four CHR RAM tables, which otherwise sit in the gaps between the frame buffers
+const CHR_MIRROR_START: u32 = 0x00078000;
+const CHR_MIRROR_END: u32 = 0x0007ffff;
+const CHR_TABLE_SIZE: u32 = 0x00002000;
+const CHR_TABLE_STRIDE: u32 = 0x00008000;
+const CHR_TABLE_OFFSET: u32 = 0x00006000;
+
+const REG_INTPND: u32 = 0x0005f800;
+const REG_INTENB: u32 = 0x0005f802;
+const REG_INTCLR: u32 = 0x0005f804;
+const REG_DPSTTS: u32 = 0x0005f820;
+const REG_DPCTRL: u32 = 0x0005f822;
+const REG_BRTA: u32 = 0x0005f824;
+const REG_BRTB: u32 = 0x0005f826;
+const REG_BRTC: u32 = 0x0005f828;
+const REG_REST: u32 = 0x0005f82a;
+const REG_FRMCYC: u32 = 0x0005f82e;
+const REG_CTA: u32 = 0x0005f830;
+const REG_XPSTTS: u32 = 0x0005f840;
+const REG_XPCTRL: u32 = 0x0005f842;
+const REG_VER: u32 = 0x0005f844;
+const REG_SPT0: u32 = 0x0005f848;
+const REG_SPT3: u32 = 0x0005f84e;
+const REG_GPLT0: u32 = 0x0005f860;
+const REG_GPLT3: u32 = 0x0005f866;
+const REG_JPLT0: u32 = 0x0005f868;
+const REG_JPLT3: u32 = 0x0005f86e;
+const REG_BKCOL: u32 = 0x0005f870;
+
+const VIP_VERSION: u16 = 2;
+
+const INT_SCANERR: u16 = 1 << 0;
+const INT_LFBEND: u16 = 1 << 1;
+const INT_RFBEND: u16 = 1 << 2;
+const INT_GAMESTART: u16 = 1 << 3;
+const INT_FRAMESTART: u16 = 1 << 4;
+const INT_SBHIT: u16 = 1 << 13;
+const INT_XPEND: u16 = 1 << 14;
+const INT_TIMEERR: u16 = 1 << 15;
+
+const DISPLAY_INTERRUPTS: u16 = INT_SCANERR | INT_LFBEND | INT_RFBEND | INT_GAMESTART | INT_FRAMESTART | INT_TIMEERR;
+const DRAWING_INTERRUPTS: u16 = INT_SBHIT | INT_XPEND | INT_TIMEERR;
+
+// The display runs at 50Hz, 20ms per frame at the CPU's 20MHz. FCLK is high for the first half of the frame and
+// each eye's frame buffer is scanned out over 5ms within its half.
+const CYCLES_PER_FRAME: usize = 400000;
+const FCLK_HIGH_END: usize = 200000;
+const LEFT_DISPLAY_START: usize = 60000;
+const LEFT_DISPLAY_END: usize = 160000;
+const RIGHT_DISPLAY_START: usize = 260000;
+const RIGHT_DISPLAY_END: usize = 360000;
+
+// Drawing a block of 8 rows takes around 200us, so a full frame is drawn in roughly 5.6ms
+const DRAWING_BLOCK_CYCLES: usize = 4000;
+
+pub struct Vip {
+    vram: Ram,
+
+    reg_intpnd: u16,
+    reg_intenb: u16,
+    reg_display_enable: bool,
+    reg_refresh_enable: bool,
+    reg_sync_enable: bool,
+    reg_column_table_lock: bool,
+    reg_brta: u8,
+    reg_brtb: u8,
+    reg_brtc: u8,
+    reg_rest: u8,
+    reg_frmcyc: u8,
+    reg_drawing_enable: bool,
+    reg_sbcmp: u8,
+    reg_spt: [u16; 4],
+    reg_gplt: [u8; 4],
+    reg_jplt: [u8; 4],
+    reg_bkcol: u8,
+
+    frame_clock: usize,
+    game_frame_counter: u8,
+    drawing_block: Option<usize>,
+    block_cycles_left: usize,
+    sbcount: u8,
+    sbout: bool,
+    overtime: bool,
+    draw_buffer: usize,
+
+    left_frame: Vec<u8>,
+    right_frame: Vec<u8>,
+}
+
+impl Vip {
+    pub fn new() -> Self {
+        Vip {
+            vram: Ram::new(VRAM_SIZE),
+
+            reg_intpnd: 0,
+            reg_intenb: 0,
+            reg_display_enable: false,
+            reg_refresh_enable: false,
+            reg_sync_enable: false,
+            reg_column_table_lock: false,
+            reg_brta: 0,
+            reg_brtb: 0,
+            reg_brtc: 0,
+            reg_rest: 0,
+            reg_frmcyc: 0,
+            reg_drawing_enable: false,
+            reg_sbcmp: 0,
+            reg_spt: [0; 4],
+            reg_gplt: [0; 4],
+            reg_jplt: [0; 4],
+            reg_bkcol: 0,
+
+            frame_clock: 0,
+            game_frame_counter: 0,
+            drawing_block: None,
+            block_cycles_left: 0,
+            sbcount: 0,
+            sbout: false,
+            overtime: false,
+            draw_buffer: 1,
+
+            left_frame: vec![0; FRAME_WIDTH * FRAME_HEIGHT],
+            right_frame: vec![0; FRAME_WIDTH * FRAME_HEIGHT],
+        }
+    }
+
+    // Advances the display and drawing processes, returning whether the VIP is requesting an interrupt
+    pub fn cycles(&mut self, cycles: usize) -> bool {
+        let mut cycles_left = cycles;
+        while cycles_left > 0 {
+            let mut step = cycles_left.min(CYCLES_PER_FRAME - self.frame_clock);
+            if self.drawing_block.is_some() {
+                step = step.min(self.block_cycles_left);
+            }
+            let start = self.frame_clock;
+            let end = start + step;
+
+            if start < LEFT_DISPLAY_END && end >= LEFT_DISPLAY_END {
+                self.display_eye(Eye::Left);
+                if self.reg_display_enable {
+                    self.raise_interrupt(INT_LFBEND);
+                }
+            }
+            if start < RIGHT_DISPLAY_END && end >= RIGHT_DISPLAY_END {
+                self.display_eye(Eye::Right);
+                if self.reg_display_enable {
+                    self.raise_interrupt(INT_RFBEND);
+                }
+            }
+
+            if let Some(block) = self.drawing_block {
+                self.block_cycles_left -= step;
+                if self.block_cycles_left == 0 {
+                    self.finish_block(block);
+                }
+            }
+
+            self.frame_clock = end;
+            if self.frame_clock == CYCLES_PER_FRAME {
+                self.frame_clock = 0;
+                self.start_frame();
+            }
+
+            cycles_left -= step;
+        }
+
+        (self.reg_intpnd & self.reg_intenb) != 0
+    }
+
+    fn start_frame(&mut self) {
+        self.raise_interrupt(INT_FRAMESTART);
+
+        if self.game_frame_counter > 0 {
+            self.game_frame_counter -= 1;
+            return;
+        }
+        self.game_frame_counter = self.reg_frmcyc;
+
+        self.raise_interrupt(INT_GAMESTART);
+        if self.drawing_block.is_some() {
+            // The previous game frame is still being drawn
+            self.overtime = true;
+            self.raise_interrupt(INT_TIMEERR);
+        } else if self.reg_drawing_enable {
+            self.overtime = false;
+
+            // Draw into the pair of frame buffers that isn't being displayed. The pairs are only swapped when drawing
+            // so with XPEN off the display keeps showing whatever the CPU has written to the current pair.
+            self.draw_buffer ^= 1;
+            self.start_block(0);
+        }
+    }
+
+    fn start_block(&mut self, block: usize) {
+        self.drawing_block = Some(block);
+        self.block_cycles_left = DRAWING_BLOCK_CYCLES;
+        self.sbcount = block as u8;
+        // SBOUT is held for as long as the block matching SBCMP is being drawn
+        self.sbout = self.sbcount == self.reg_sbcmp;
+        if self.sbout {
+            self.raise_interrupt(INT_SBHIT);
+        }
+
+        self.draw_block(self.draw_buffer, block);
+    }
+
+    fn finish_block(&mut self, block: usize) {
+        self.sbout = false;
+
+        if !self.reg_drawing_enable {
+            // Turning XPEN off stops drawing once the current block is done, leaving the rest of the frame buffers
+            // untouched for the CPU to draw into directly
+            self.drawing_block = None;
+        } else if block + 1 < NUM_DRAWING_BLOCKS {
+            self.start_block(block + 1);
+        } else {
+            self.drawing_block = None;
+            self.raise_interrupt(INT_XPEND);
+        }
+    }
+
+    fn raise_interrupt(&mut self, interrupt: u16) {
+        self.reg_intpnd |= interrupt;
+    }
+
+    // A byte of VRAM or a register, or None if nothing is mapped at addr
+    fn read(&self, addr: u32) -> Option<u8> {
+        let addr = addr & 0x0007ffff;
+        if let IO_START..=IO_END = addr {
+            let val = self.read_register(addr & 0xfffffffe);
+            return Some(if (addr & 0x01) != 0 { (val >> 8) as u8 } else { val as u8 });
+        }
+
+        vram_addr(addr).map(|addr| self.vram.read_byte(addr))
+    }
+
+    fn read_register(&self, addr: u32) -> u16 {
+        match addr {
+            REG_INTPND => self.reg_intpnd,
+            REG_INTENB => self.reg_intenb,
+            REG_DPSTTS => self.reg_dpstts(),
+            REG_BRTA => self.reg_brta as u16,
+            REG_BRTB => self.reg_brtb as u16,
+            REG_BRTC => self.reg_brtc as u16,
+            REG_REST => self.reg_rest as u16,
+            REG_FRMCYC => self.reg_frmcyc as u16,
+            REG_CTA => self.reg_cta(),
+            REG_XPSTTS => self.reg_xpstts(),
+            REG_VER => VIP_VERSION,
+            REG_SPT0..=REG_SPT3 => self.reg_spt[((addr - REG_SPT0) / 2) as usize],
+            REG_GPLT0..=REG_GPLT3 => self.reg_gplt[((addr - REG_GPLT0) / 2) as usize] as u16,
+            REG_JPLT0..=REG_JPLT3 => self.reg_jplt[((addr - REG_JPLT0) / 2) as usize] as u16,
+            REG_BKCOL => self.reg_bkcol as u16,
+            _ => {
+                println!("WARN: Reading from unknown VIP register [0x{:08x}]", addr);
+                0
+            }
+        }
+    }
+
+    fn write_register(&mut self, addr: u32, val: u16) {
+        match addr {
+            REG_INTENB => self.reg_intenb = val & (DISPLAY_INTERRUPTS | DRAWING_INTERRUPTS),
+            REG_INTCLR => self.reg_intpnd &= !val,
+            REG_DPCTRL => {
+                if (val & 0x0001) != 0 {
+                    self.reg_intenb &= !DISPLAY_INTERRUPTS;
+                    self.reg_intpnd &= !DISPLAY_INTERRUPTS;
+                }
+                self.reg_display_enable = (val & 0x0002) != 0;
+                self.reg_refresh_enable = (val & 0x0100) != 0;
+                self.reg_sync_enable = (val & 0x0200) != 0;
+                self.reg_column_table_lock = (val & 0x0400) != 0;
+            }
+            REG_BRTA => self.reg_brta = val as u8,
+            REG_BRTB => self.reg_brtb = val as u8,
+            REG_BRTC => self.reg_brtc = val as u8,
+            REG_REST => self.reg_rest = val as u8,
+            REG_FRMCYC => self.reg_frmcyc = (val & 0x000f) as u8,
+            REG_XPCTRL => {
+                if (val & 0x0001) != 0 {
+                    self.reg_intenb &= !DRAWING_INTERRUPTS;
+                    self.reg_intpnd &= !DRAWING_INTERRUPTS;
+                    self.drawing_block = None;
+                    self.sbout = false;
+                    self.overtime = false;
+                }
+                self.reg_drawing_enable = (val & 0x0002) != 0;
+                self.reg_sbcmp = ((val >> 8) & 0x1f) as u8;
+            }
+            REG_SPT0..=REG_SPT3 => self.reg_spt[((addr - REG_SPT0) / 2) as usize] = val & 0x03ff,
+            REG_GPLT0..=REG_GPLT3 => self.reg_gplt[((addr - REG_GPLT0) / 2) as usize] = (val & 0x00fc) as u8,
+            REG_JPLT0..=REG_JPLT3 => self.reg_jplt[((addr - REG_JPLT0) / 2) as usize] = (val & 0x00fc) as u8,
+            REG_BKCOL => self.reg_bkcol = (val & 0x0003) as u8,
+            REG_INTPND | REG_DPSTTS | REG_CTA | REG_XPSTTS | REG_VER => {
+                println!("WARN: Write to read only VIP register [0x{:08x}] = 0x{:04x}", addr, val);
+            }
+            _ => println!("WARN: Write to unknown VIP register [0x{:08x}] = 0x{:04x}", addr, val),
+        }
+    }
+
+    fn reg_dpstts(&self) -> u16 {
+        let mut val = 0;
+        val |= if self.reg_display_enable { 1 << 1 } else { 0 };
+        val |= self.display_busy();
+        val |= 1 << 6; // SCANRDY, the mirrors are always stable
+        val |= if self.frame_clock < FCLK_HIGH_END { 1 << 7 } else { 0 };
+        val |= if self.reg_refresh_enable { 1 << 8 } else { 0 };
+        val |= if self.reg_sync_enable { 1 << 9 } else { 0 };
+        val |= if self.reg_column_table_lock { 1 << 10 } else { 0 };
+        val
+    }
+
+    fn reg_xpstts(&self) -> u16 {
+        let mut val = 0;
+        val |= if self.reg_drawing_enable { 1 << 1 } else { 0 };
+        val |= if self.drawing_block.is_some() { 1 << (2 + self.draw_buffer) } else { 0 };
+        val |= if self.overtime { 1 << 4 } else { 0 };
+        val |= (self.sbcount as u16) << 8;
+        val |= if self.sbout { 1 << 15 } else { 0 };
+        val
+    }
+
+    // The L0BSY, R0BSY, L1BSY and R1BSY bits for whichever frame buffer is currently being scanned out
+    fn display_busy(&self) -> u16 {
+        if !self.reg_display_enable {
+            return 0;
+        }
+
+        let display_buffer = (self.draw_buffer ^ 1) as u16;
+        if (LEFT_DISPLAY_START..LEFT_DISPLAY_END).contains(&self.frame_clock) {
+            1 << (2 + display_buffer * 2)
+        } else if (RIGHT_DISPLAY_START..RIGHT_DISPLAY_END).contains(&self.frame_clock) {
+            1 << (3 + display_buffer * 2)
+        } else {
+            0
+        }
+    }
+}
+
+impl BusDevice for Vip {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        self.read(addr).unwrap_or_else(|| {
+            println!("WARN: Reading from unmapped VIP memory [0x{:08x}]", addr);
+            0
+        })
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        let addr = addr & 0x0007fffe;
+        if let IO_START..=IO_END = addr {
+            return self.read_register(addr);
+        }
+
+        match vram_addr(addr) {
+            Some(addr) => self.vram.read_halfword(addr),
+            None => {
+                println!("WARN: Reading from unmapped VIP memory [0x{:08x}]", addr);
+                0
+            }
+        }
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        let addr = addr & 0x0007ffff;
+        if let IO_START..=IO_END = addr {
+            // The registers sit on the low half of the data bus
+            if (addr & 0x01) == 0 {
+                self.write_register(addr, val as u16);
+            }
+            return;
+        }
+
+        match vram_addr(addr) {
+            Some(addr) => self.vram.write_byte(addr, val),
+            None => println!("WARN: Writing to unmapped VIP memory [0x{:08x}] = {}/0x{:02x}", addr, val, val),
+        }
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        let addr = addr & 0x0007fffe;
+        if let IO_START..=IO_END = addr {
+            self.write_register(addr, val);
+            return;
+        }
+
+        match vram_addr(addr) {
+            Some(addr) => self.vram.write_halfword(addr, val),
+            None => println!("WARN: Writing to unmapped VIP memory [0x{:08x}] = {}/0x{:04x}", addr, val, val),
+        }
+    }
+
+    fn peek(&self, addr: u32) -> u8 {
+        self.read(addr).unwrap_or(0)
+    }
+}
+
+// Maps a VIP address onto its location in VRAM, the VIP address space mirrors every 512KiB
+fn vram_addr(addr: u32) -> Option<u32> {
+    let addr = addr & 0x0007ffff;
+    match addr {
+        VRAM_START..=VRAM_END => Some(addr),
+        CHR_MIRROR_START..=CHR_MIRROR_END => {
+            let offset = addr - CHR_MIRROR_START;
+            let table = offset / CHR_TABLE_SIZE;
+            Some(CHR_TABLE_OFFSET + table * CHR_TABLE_STRIDE + (offset % CHR_TABLE_SIZE))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::world::{FRAME_BUFFER_LEFT, FRAME_BUFFER_RIGHT};
+
+    fn enable_display(vip: &mut Vip) {
+        vip.write_halfword(REG_DPCTRL, 0x0002);
+    }
+
+    // Advances to the given point later in the current frame
+    fn run_until(vip: &mut Vip, frame_clock: usize) {
+        vip.cycles(frame_clock - vip.frame_clock);
+    }
+
+    #[test]
+    fn column_table_address() {
+        let mut vip = Vip::new();
+        enable_display(&mut vip);
+        assert_eq!(vip.read_halfword(REG_CTA), 0xffff);
+
+        // Half way through the left eye's 384 columns, two to an entry
+        run_until(&mut vip, (LEFT_DISPLAY_START + LEFT_DISPLAY_END) / 2);
+        assert_eq!(vip.read_halfword(REG_CTA), 0xff9f);
+
+        run_until(&mut vip, RIGHT_DISPLAY_START + 1);
+        assert_eq!(vip.read_halfword(REG_CTA), 0xff40);
+
+        run_until(&mut vip, RIGHT_DISPLAY_END);
+        assert_eq!(vip.read_halfword(REG_CTA), 0x4040);
+
+        // Both reset at the start of the next frame
+        vip.cycles(CYCLES_PER_FRAME - vip.frame_clock);
+        assert_eq!(vip.read_halfword(REG_CTA), 0xffff);
+    }
+
+    #[test]
+    fn display_column_timing() {
+        let mut vip = Vip::new();
+        enable_display(&mut vip);
+        vip.write_halfword(REG_BRTA, 10);
+        vip.write_halfword(REG_BRTB, 20);
+        vip.write_halfword(REG_BRTC, 30);
+
+        // In the left column table the first column pair is given long enough for every pulse, the rest are cut short
+        // part way through BRTB
+        for entry in 0..0x100 {
+            vip.write_halfword(0x0003dc00 + entry * 2, 0x000f);
+        }
+        vip.write_halfword(0x0003ddfe, 0x00ff);
+        // Colour 3 in the top left pixel and in the third column
+        vip.write_byte(0x00000000, 0x03);
+        vip.write_byte(0x00000080, 0x03);
+
+        let frame = vip.frame(Eye::Left).as_ptr();
+        run_until(&mut vip, LEFT_DISPLAY_END);
+        assert_eq!(vip.frame(Eye::Left).as_ptr(), frame);
+        assert_eq!(vip.frame(Eye::Left)[0], 60);
+        assert_eq!(vip.frame(Eye::Left)[1], 0);
+        assert_eq!(vip.frame(Eye::Left)[2], 14);
+    }
+
+    #[test]
+    fn sbout_follows_sbcmp_block() {
+        let mut vip = Vip::new();
+        vip.write_halfword(REG_INTENB, INT_SBHIT);
+        vip.write_halfword(REG_XPCTRL, 0x0202);
+
+        // Drawing starts on block 0 at the next game frame
+        vip.cycles(CYCLES_PER_FRAME);
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x0000);
+
+        vip.cycles(DRAWING_BLOCK_CYCLES - 1);
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x0000);
+        assert!(!vip.cycles(DRAWING_BLOCK_CYCLES));
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x0100);
+
+        // SBOUT and SBHIT come on as block 2 starts and SBOUT goes off again once it's done
+        assert!(vip.cycles(1));
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x8200);
+        assert_ne!(vip.read_halfword(REG_INTPND) & INT_SBHIT, 0);
+        vip.cycles(DRAWING_BLOCK_CYCLES - 1);
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x8200);
+        vip.cycles(1);
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x9f00, 0x0300);
+    }
+
+    // Runs until a whole game frame has been drawn
+    fn draw_frame(vip: &mut Vip) {
+        vip.cycles(CYCLES_PER_FRAME - vip.frame_clock);
+        vip.cycles(NUM_DRAWING_BLOCKS * DRAWING_BLOCK_CYCLES);
+    }
+
+    #[test]
+    fn direct_frame_buffer_writes_without_drawing() {
+        let mut vip = Vip::new();
+        enable_display(&mut vip);
+        vip.write_halfword(REG_BRTA, 32);
+        vip.write_halfword(REG_BKCOL, 3);
+        for entry in 0..0x100 {
+            vip.write_halfword(0x0003dc00 + entry * 2, 0x00ff);
+        }
+
+        // With XPEN off nothing swaps or clears the frame buffers, so what the CPU writes is what's displayed
+        vip.write_halfword(0x00000000, 0x0001);
+        vip.write_halfword(0x00010000, 0x0001);
+        draw_frame(&mut vip);
+        draw_frame(&mut vip);
+
+        assert_eq!(vip.read_halfword(0x00000000), 0x0001);
+        assert_eq!(vip.read_halfword(0x00010000), 0x0001);
+        assert_eq!(vip.read_halfword(REG_XPSTTS) & 0x000c, 0);
+        assert_eq!(vip.frame(Eye::Left)[0], 32);
+        assert_eq!(vip.frame(Eye::Left)[FRAME_WIDTH], 0);
+    }
+
+    #[test]
+    fn drawing_clears_to_bkcol() {
+        let mut vip = Vip::new();
+        vip.write_halfword(REG_BKCOL, 2);
+        // Direct writes to the pair about to be drawn, and to CHR table 0 in the gap after the left frame buffer
+        vip.write_halfword(0x00000000, 0xffff);
+        vip.write_halfword(0x00005ff6, 0xffff);
+        vip.write_halfword(0x00005ffe, 0xffff);
+        vip.write_halfword(0x00010000, 0xffff);
+        vip.write_halfword(0x00006000, 0x1234);
+
+        vip.write_halfword(REG_XPCTRL, 0x0002);
+        draw_frame(&mut vip);
+
+        assert_eq!(vip.read_halfword(0x00000000), 0xaaaa);
+        assert_eq!(vip.read_halfword(0x00005ff6), 0xaaaa);
+        assert_eq!(vip.read_halfword(0x00010000), 0xaaaa);
+        // Only the 224 displayed rows of each column are drawn, the 32 below them keep whatever was written
+        assert_eq!(vip.read_halfword(0x00005ffe), 0xffff);
+        assert_eq!(vip.read_halfword(0x00006000), 0x1234);
+        // The other pair is left alone for the display
+        assert_eq!(vip.read_halfword(0x00008000), 0x0000);
+    }
+
+    #[test]
+    fn chr_and_bg_map_writes_shadow_drawing() {
+        let mut vip = Vip::new();
+        // CHR tables written through the linear mirror appear in the gaps after each frame buffer, and vice versa
+        vip.write_halfword(0x00078010, 0x0001);
+        assert_eq!(vip.read_halfword(0x00006010), 0x0001);
+        vip.write_halfword(0x0000e000, 0xbeef);
+        assert_eq!(vip.read_halfword(0x0007a000), 0xbeef);
+
+        // World 31 shows character 1 from BG map 0 in the top left, world 30 ends the list
+        vip.write_halfword(0x00020000, 0x0001);
+        vip.write_halfword(0x0003dbe0, 0x8000);
+        vip.write_halfword(0x0003dbee, 7);
+        vip.write_halfword(0x0003dbf0, 7);
+        vip.write_halfword(0x0003dbc0, 0x0040);
+        vip.write_halfword(REG_GPLT0, 0xe4);
+
+        vip.write_halfword(REG_XPCTRL, 0x0002);
+        draw_frame(&mut vip);
+        assert_eq!(vip.read_pixel(FRAME_BUFFER_LEFT[0], 0, 0), 1);
+        assert_eq!(vip.read_pixel(FRAME_BUFFER_LEFT[0], 1, 0), 0);
+        assert_eq!(vip.read_pixel(FRAME_BUFFER_RIGHT[0], 0, 0), 0);
+    }
+}
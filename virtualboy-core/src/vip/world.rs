@@ -0,0 +1,347 @@
+use std::ops::Range;
+
+use super::Vip;
+
+pub const DISPLAY_WIDTH: i32 = 384;
+pub const DISPLAY_HEIGHT: i32 = 224;
+
+pub const FRAME_BUFFER_LEFT: [u32; 2] = [0x00000000, 0x00008000];
+pub const FRAME_BUFFER_RIGHT: [u32; 2] = [0x00010000, 0x00018000];
+// Frame buffers are stored column first, each column taking 64 bytes with 4 pixels per byte
+const FRAME_BUFFER_COLUMN_SIZE: u32 = 64;
+
+// Drawing works through the display in blocks of 8 rows
+pub const DRAWING_BLOCK_ROWS: i32 = 8;
+pub const NUM_DRAWING_BLOCKS: usize = (DISPLAY_HEIGHT / DRAWING_BLOCK_ROWS) as usize;
+
+const CHR_TABLE_OFFSET: u32 = 0x00006000;
+const CHR_TABLE_STRIDE: u32 = 0x00008000;
+const CHARS_PER_TABLE: u32 = 512;
+const CHAR_SIZE: u32 = 16;
+
+const BG_MAPS: u32 = 0x00020000;
+const BG_MAP_SIZE: u32 = 0x00002000;
+const BG_MAP_PIXELS: i32 = 512;
+const BG_MAP_CELLS: i32 = 64;
+
+const WORLD_ATTRIBUTES: u32 = 0x0003d800;
+const WORLD_ATTRIBUTES_SIZE: u32 = 32;
+const NUM_WORLDS: u32 = 32;
+
+const HBIAS_PARAM_SIZE: u32 = 4;
+const AFFINE_PARAM_SIZE: u32 = 16;
+
+const OAM: u32 = 0x0003e000;
+const OBJ_ATTRIBUTES_SIZE: u32 = 8;
+const NUM_OBJ_GROUPS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldMode {
+    Normal,
+    HBias,
+    Affine,
+    Object,
+}
+
+pub struct Object {
+    pub jx: i32,
+    pub jp: i32,
+    pub jy: i32,
+    pub left_enable: bool,
+    pub right_enable: bool,
+    pub palette: u8,
+    pub horizontal_flip: bool,
+    pub vertical_flip: bool,
+    pub char_index: u32,
+}
+
+pub struct World {
+    pub left_enable: bool,
+    pub right_enable: bool,
+    pub mode: WorldMode,
+    pub scx: u32,
+    pub scy: u32,
+    pub overplane: bool,
+    pub end: bool,
+    pub map_base: u32,
+    pub gx: i32,
+    pub gp: i32,
+    pub gy: i32,
+    pub mx: i32,
+    pub mp: i32,
+    pub my: i32,
+    pub width: i32,
+    pub height: i32,
+    pub param_base: u32,
+    pub overplane_cell: u32,
+}
+
+impl Vip {
+    // Draws one 8 row block of the display into the given pair of frame buffers
+    pub(super) fn draw_block(&mut self, buffer: usize, block: usize) {
+        let start = block as i32 * DRAWING_BLOCK_ROWS;
+        let rows = start..start + DRAWING_BLOCK_ROWS;
+
+        self.clear_block(buffer, block);
+
+        // Each successive OBJ world draws the next group of objects down, starting from SPT3
+        let mut obj_groups = (0..NUM_OBJ_GROUPS).rev();
+
+        for index in (0..NUM_WORLDS).rev() {
+            let world = self.world(index);
+            if world.end {
+                break;
+            }
+            if !world.left_enable && !world.right_enable {
+                continue;
+            }
+
+            match world.mode {
+                WorldMode::Normal | WorldMode::HBias | WorldMode::Affine => self.draw_bg_world(&world, buffer, &rows),
+                WorldMode::Object => match obj_groups.next() {
+                    Some(group) => self.draw_obj_world(&world, group, buffer, &rows),
+                    None => println!("WARN: More than {} OBJ worlds, world {} not drawn", NUM_OBJ_GROUPS, index),
+                },
+            }
+        }
+    }
+
+    fn world(&self, index: u32) -> World {
+        let addr = WORLD_ATTRIBUTES + index * WORLD_ATTRIBUTES_SIZE;
+        let attr = |offset: u32| self.vram.read_halfword(addr + offset * 2);
+
+        let header = attr(0);
+        World {
+            left_enable: (header & 0x8000) != 0,
+            right_enable: (header & 0x4000) != 0,
+            mode: match (header >> 12) & 0x03 {
+                0 => WorldMode::Normal,
+                1 => WorldMode::HBias,
+                2 => WorldMode::Affine,
+                _ => WorldMode::Object,
+            },
+            scx: ((header >> 10) & 0x03) as u32,
+            scy: ((header >> 8) & 0x03) as u32,
+            overplane: (header & 0x0080) != 0,
+            end: (header & 0x0040) != 0,
+            map_base: (header & 0x000f) as u32,
+            gx: sign_extend(attr(1), 10),
+            gp: sign_extend(attr(2), 10),
+            gy: attr(3) as i16 as i32,
+            mx: sign_extend(attr(4), 13),
+            mp: sign_extend(attr(5), 15),
+            my: sign_extend(attr(6), 13),
+            width: (attr(7) & 0x1fff) as i32 + 1,
+            height: attr(8) as i32 + 1,
+            param_base: attr(9) as u32,
+            overplane_cell: attr(10) as u32,
+        }
+    }
+
+    fn object(&self, index: u32) -> Object {
+        let addr = OAM + index * OBJ_ATTRIBUTES_SIZE;
+        let attr = |offset: u32| self.vram.read_halfword(addr + offset * 2);
+
+        let jy = (attr(2) & 0x00ff) as i32;
+        let cell = attr(3);
+        Object {
+            jx: sign_extend(attr(0), 10),
+            jp: sign_extend(attr(1), 10),
+            // JY is 8 bits wide, the bottom of the range wraps round above the top of the screen
+            jy: if jy >= 0xe0 { jy - 0x100 } else { jy },
+            left_enable: (attr(1) & 0x8000) != 0,
+            right_enable: (attr(1) & 0x4000) != 0,
+            palette: self.reg_jplt[(cell >> 14) as usize],
+            horizontal_flip: (cell & 0x2000) != 0,
+            vertical_flip: (cell & 0x1000) != 0,
+            char_index: (cell & 0x07ff) as u32,
+        }
+    }
+
+    fn clear_block(&mut self, buffer: usize, block: usize) {
+        let val = self.reg_bkcol * 0x55;
+        let block_bytes = (DRAWING_BLOCK_ROWS / 4) as u32;
+        for &base in [FRAME_BUFFER_LEFT[buffer], FRAME_BUFFER_RIGHT[buffer]].iter() {
+            for x in 0..DISPLAY_WIDTH as u32 {
+                let addr = base + x * FRAME_BUFFER_COLUMN_SIZE + block as u32 * block_bytes;
+                for offset in 0..block_bytes {
+                    self.vram.write_byte(addr + offset, val);
+                }
+            }
+        }
+    }
+
+    fn draw_bg_world(&mut self, world: &World, buffer: usize, rows: &Range<i32>) {
+        for &eye in [Eye::Left, Eye::Right].iter() {
+            let (enabled, frame_buffer, parallax) = match eye {
+                Eye::Left => (world.left_enable, FRAME_BUFFER_LEFT[buffer], -world.gp),
+                Eye::Right => (world.right_enable, FRAME_BUFFER_RIGHT[buffer], world.gp),
+            };
+            if !enabled {
+                continue;
+            }
+
+            for y in rows.start.max(world.gy)..rows.end.min(world.gy + world.height) {
+                let dy = y - world.gy;
+
+                for dx in 0..world.width {
+                    let x = world.gx + parallax + dx;
+                    if !(0..DISPLAY_WIDTH).contains(&x) {
+                        continue;
+                    }
+
+                    let (bg_x, bg_y) = self.bg_coordinates(world, eye, dx, dy);
+                    if let Some(color) = self.bg_pixel(world, bg_x, bg_y) {
+                        self.write_pixel(frame_buffer, x, y, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Draws the objects in the given SPT group from the highest index down, so that lower numbered objects
+    // end up in front
+    fn draw_obj_world(&mut self, world: &World, group: usize, buffer: usize, rows: &Range<i32>) {
+        let first = if group == 0 { 0 } else { (self.reg_spt[group - 1] as u32 + 1) & 0x03ff };
+        let last = self.reg_spt[group] as u32;
+        if first > last {
+            return;
+        }
+
+        for index in (first..=last).rev() {
+            let object = self.object(index);
+
+            for &eye in [Eye::Left, Eye::Right].iter() {
+                let (enabled, frame_buffer, parallax) = match eye {
+                    Eye::Left => (world.left_enable && object.left_enable, FRAME_BUFFER_LEFT[buffer], -object.jp),
+                    Eye::Right => (world.right_enable && object.right_enable, FRAME_BUFFER_RIGHT[buffer], object.jp),
+                };
+                if !enabled {
+                    continue;
+                }
+
+                for cy in 0..8 {
+                    let y = object.jy + cy;
+                    if !rows.contains(&y) {
+                        continue;
+                    }
+
+                    for cx in 0..8 {
+                        let x = object.jx + parallax + cx;
+                        if !(0..DISPLAY_WIDTH).contains(&x) {
+                            continue;
+                        }
+
+                        let pixel = self.char_pixel(object.char_index, cx, cy, object.horizontal_flip, object.vertical_flip);
+                        if pixel != 0 {
+                            self.write_pixel(frame_buffer, x, y, (object.palette >> (pixel * 2)) & 0x03);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Maps a pixel within a world's window onto its BG map coordinates
+    fn bg_coordinates(&self, world: &World, eye: Eye, dx: i32, dy: i32) -> (i32, i32) {
+        let parallax = match eye {
+            Eye::Left => -world.mp,
+            Eye::Right => world.mp,
+        };
+
+        match world.mode {
+            WorldMode::Normal => (world.mx + parallax + dx, world.my + dy),
+            WorldMode::HBias => {
+                let addr = BG_MAPS + world.param_base * 2 + dy as u32 * HBIAS_PARAM_SIZE;
+                let offset = match eye {
+                    Eye::Left => self.vram.read_halfword(addr),
+                    Eye::Right => self.vram.read_halfword(addr + 2),
+                };
+                (world.mx + parallax + dx + sign_extend(offset, 13), world.my + dy)
+            }
+            WorldMode::Affine => {
+                let addr = BG_MAPS + world.param_base * 2 + dy as u32 * AFFINE_PARAM_SIZE;
+                let param = |offset: u32| self.vram.read_halfword(addr + offset * 2) as i16 as i32;
+
+                // MX and MY are 13.3 fixed point and DX and DY are 7.9 fixed point
+                let mx = param(0) << 6;
+                let mp = param(1);
+                let my = param(2) << 6;
+                let step_x = param(3);
+                let step_y = param(4);
+
+                // Parallax only shifts the eye it points away from
+                let shift = match eye {
+                    Eye::Left if mp < 0 => -mp,
+                    Eye::Right if mp > 0 => mp,
+                    _ => 0,
+                };
+                let dx = dx + shift;
+                ((mx + step_x * dx) >> 9, (my + step_y * dx) >> 9)
+            }
+            WorldMode::Object => unreachable!(),
+        }
+    }
+
+    // Looks up the colour at the given BG map coordinates, or None if the pixel is transparent
+    fn bg_pixel(&self, world: &World, x: i32, y: i32) -> Option<u8> {
+        let width = BG_MAP_PIXELS << world.scx;
+        let height = BG_MAP_PIXELS << world.scy;
+
+        let cell_addr = if world.overplane && (x < 0 || x >= width || y < 0 || y >= height) {
+            BG_MAPS + world.overplane_cell * 2
+        } else {
+            let x = x & (width - 1);
+            let y = y & (height - 1);
+            let map = world.map_base + ((y / BG_MAP_PIXELS) << world.scx) as u32 + (x / BG_MAP_PIXELS) as u32;
+            let cell = ((y % BG_MAP_PIXELS) / 8) * BG_MAP_CELLS + (x % BG_MAP_PIXELS) / 8;
+            BG_MAPS + map * BG_MAP_SIZE + cell as u32 * 2
+        };
+        let cell = self.vram.read_halfword(cell_addr);
+
+        let palette = self.reg_gplt[(cell >> 14) as usize];
+        let horizontal_flip = (cell & 0x2000) != 0;
+        let vertical_flip = (cell & 0x1000) != 0;
+        let pixel = self.char_pixel((cell & 0x07ff) as u32, x & 7, y & 7, horizontal_flip, vertical_flip);
+
+        if pixel == 0 {
+            None
+        } else {
+            Some((palette >> (pixel * 2)) & 0x03)
+        }
+    }
+
+    fn char_pixel(&self, index: u32, x: i32, y: i32, horizontal_flip: bool, vertical_flip: bool) -> u8 {
+        let x = if horizontal_flip { 7 - x } else { x };
+        let y = if vertical_flip { 7 - y } else { y };
+
+        let table = index / CHARS_PER_TABLE;
+        let addr = CHR_TABLE_OFFSET + table * CHR_TABLE_STRIDE + (index % CHARS_PER_TABLE) * CHAR_SIZE;
+        let row = self.vram.read_halfword(addr + y as u32 * 2);
+        ((row >> (x * 2)) & 0x03) as u8
+    }
+
+    pub(super) fn read_pixel(&self, frame_buffer: u32, x: i32, y: i32) -> u8 {
+        let addr = frame_buffer + x as u32 * FRAME_BUFFER_COLUMN_SIZE + (y as u32 / 4);
+        let shift = (y as u32 % 4) * 2;
+        (self.vram.read_byte(addr) >> shift) & 0x03
+    }
+
+    fn write_pixel(&mut self, frame_buffer: u32, x: i32, y: i32, color: u8) {
+        let addr = frame_buffer + x as u32 * FRAME_BUFFER_COLUMN_SIZE + (y as u32 / 4);
+        let shift = (y as u32 % 4) * 2;
+        let val = self.vram.read_byte(addr);
+        self.vram.write_byte(addr, (val & !(0x03 << shift)) | (color << shift));
+    }
+}
+
+fn sign_extend(val: u16, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((val as i32) << shift) >> shift
+}
@@ -1,7 +1,17 @@
+use std::io;
+use std::path::Path;
+
 use super::rom::Rom;
 use super::interconnect::Interconnect;
 use super::v810::V810;
 
+pub use super::audio::NATIVE_SAMPLE_RATE;
+pub use super::game_pad::Buttons;
+pub use super::link::{InProcessLink, LinkCable};
+pub use super::ram::RamInit;
+pub use super::sram::{default_sram_size, save_file_path, DEFAULT_SRAM_SIZE, MAX_SRAM_SIZE};
+pub use super::vip::{Eye, FRAME_WIDTH, FRAME_HEIGHT};
+
 pub struct VirtualBoy {
     pub interconnect: Interconnect,
     pub cpu: V810,
@@ -27,4 +37,66 @@ impl VirtualBoy {
 
         cycles
     }
-}
\ No newline at end of file
+
+    // The most recently displayed image for an eye, FRAME_WIDTH x FRAME_HEIGHT LED intensities stored row by row
+    pub fn frame(&self, eye: Eye) -> &[u8] {
+        self.interconnect.frame(eye)
+    }
+
+    // Rate of the samples handed out by read_samples, NATIVE_SAMPLE_RATE unless changed with set_sample_rate
+    pub fn sample_rate(&self) -> u32 {
+        self.interconnect.sample_rate()
+    }
+
+    // Resamples the VSU output to the given rate, which must be non-zero, discarding any samples not yet read
+    pub fn set_sample_rate(&mut self, sample_rate: u32) -> io::Result<()> {
+        self.interconnect.set_sample_rate(sample_rate)
+    }
+
+    pub fn samples_available(&self) -> usize {
+        self.interconnect.samples_available()
+    }
+
+    // Drains buffered audio into out as interleaved left/right i16 samples, returning the number of samples written
+    pub fn read_samples(&mut self, out: &mut [i16]) -> usize {
+        self.interconnect.read_samples(out)
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.interconnect.set_buttons(buttons);
+    }
+
+    pub fn set_low_battery(&mut self, low_battery: bool) {
+        self.interconnect.set_low_battery(low_battery);
+    }
+
+    // Plugs a cable into the link port, replacing any already connected
+    pub fn connect_link(&mut self, cable: Box<dyn LinkCable + Send>) {
+        self.interconnect.connect_link(cable);
+    }
+
+    pub fn disconnect_link(&mut self) {
+        self.interconnect.disconnect_link();
+    }
+
+    // Resizes the cartridge RAM, keeping its attached save file and as much of its contents as fit. The size must
+    // be a power of two no larger than MAX_SRAM_SIZE.
+    pub fn set_sram_size(&mut self, size: u32) -> io::Result<()> {
+        self.interconnect.set_sram_size(size)
+    }
+
+    // Keeps the cartridge RAM in a save file, loading it now if it exists. Saves are written back by
+    // flush_save_file and when the VirtualBoy is dropped.
+    pub fn attach_save_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.interconnect.attach_save_file(path)
+    }
+
+    pub fn flush_save_file(&mut self) -> io::Result<()> {
+        self.interconnect.flush_save_file()
+    }
+
+    // Refills system WRAM, which otherwise powers on cleared to zero
+    pub fn init_wram(&mut self, init: RamInit) {
+        self.interconnect.init_wram(init);
+    }
+}
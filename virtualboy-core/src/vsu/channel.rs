@@ -0,0 +1,343 @@
+// The VSU clocks its channels in units of CPU cycles, the wave channels step through their samples at 5MHz and
+// the noise channel shifts its LFSR at 500kHz
+const WAVE_CLOCK_DIVIDER: usize = 4;
+const NOISE_CLOCK_DIVIDER: usize = 40;
+
+pub const NUM_SAMPLES: usize = 32;
+pub const MAX_SAMPLE: u8 = 0x3f;
+
+// The bit fed back into the noise LFSR along with bit 7, selected by bits 4-6 of S6EV1
+const NOISE_TAPS: [u32; 8] = [14, 10, 13, 4, 8, 6, 9, 11];
+const NOISE_LFSR_RESET: u32 = 0x7fff;
+
+const MAX_ENVELOPE: u8 = 0x0f;
+const MAX_FREQUENCY: u16 = 0x07ff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    Wave,
+    SweepModulation,
+    Noise,
+}
+
+pub struct Channel {
+    kind: ChannelKind,
+
+    pub reg_int: u8,
+    pub reg_left_volume: u8,
+    pub reg_right_volume: u8,
+    pub reg_frequency: u16,
+    pub reg_ev0: u8,
+    pub reg_ev1: u8,
+    pub reg_ram: u8,
+    pub reg_swp: u8,
+
+    enabled: bool,
+    interval_counter: u8,
+    envelope_level: u8,
+    envelope_counter: u8,
+    sweep_modulation_counter: usize,
+    modulation_index: usize,
+    // The frequency actually being played, which sweep and modulation move away from the one written to S*FQ
+    frequency: u16,
+    frequency_counter: usize,
+    sample_index: usize,
+    lfsr: u32,
+}
+
+impl Channel {
+    pub fn new(kind: ChannelKind) -> Self {
+        Channel {
+            kind,
+
+            reg_int: 0,
+            reg_left_volume: 0,
+            reg_right_volume: 0,
+            reg_frequency: 0,
+            reg_ev0: 0,
+            reg_ev1: 0,
+            reg_ram: 0,
+            reg_swp: 0,
+
+            enabled: false,
+            interval_counter: 0,
+            envelope_level: 0,
+            envelope_counter: 0,
+            sweep_modulation_counter: 0,
+            modulation_index: 0,
+            frequency: 0,
+            frequency_counter: 0,
+            sample_index: 0,
+            lfsr: NOISE_LFSR_RESET,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Writing S*INT (re)starts the channel if its enable bit is set
+    pub fn write_int(&mut self, val: u8) {
+        self.reg_int = val & 0xbf;
+        self.enabled = (val & 0x80) != 0;
+        if self.enabled {
+            self.interval_counter = val & 0x1f;
+            self.envelope_level = self.reg_ev0 >> 4;
+            self.envelope_counter = self.reg_ev0 & 0x07;
+            self.sweep_modulation_counter = self.sweep_modulation_interval();
+            self.modulation_index = 0;
+            self.frequency = self.reg_frequency;
+            self.frequency_counter = self.period();
+            self.sample_index = 0;
+            self.lfsr = NOISE_LFSR_RESET;
+        }
+    }
+
+    pub fn write_frequency_low(&mut self, val: u8) {
+        self.reg_frequency = (self.reg_frequency & 0x0700) | val as u16;
+        self.frequency = self.reg_frequency;
+    }
+
+    pub fn write_frequency_high(&mut self, val: u8) {
+        self.reg_frequency = (self.reg_frequency & 0x00ff) | (((val & 0x07) as u16) << 8);
+        self.frequency = self.reg_frequency;
+    }
+
+    pub fn write_ev0(&mut self, val: u8) {
+        self.reg_ev0 = val;
+        self.envelope_level = val >> 4;
+    }
+
+    pub fn stop(&mut self) {
+        self.enabled = false;
+    }
+
+    pub fn cycles(&mut self, cycles: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut cycles_left = cycles;
+        while cycles_left >= self.frequency_counter {
+            cycles_left -= self.frequency_counter;
+            self.frequency_counter = self.period();
+            self.step();
+        }
+        self.frequency_counter -= cycles_left;
+    }
+
+    // Counts down the interval on each 3.84ms tick, stopping the channel when it runs out if auto shutoff is on
+    pub fn interval_tick(&mut self) {
+        if !self.enabled || (self.reg_int & 0x20) == 0 {
+            return;
+        }
+
+        if self.interval_counter == 0 {
+            self.enabled = false;
+        } else {
+            self.interval_counter -= 1;
+        }
+    }
+
+    // Steps the envelope towards silence or full volume every (n + 1) 15.36ms ticks, restarting it from the
+    // initial value once it reaches the end if repeat is set
+    pub fn envelope_tick(&mut self) {
+        if !self.enabled || (self.reg_ev1 & 0x01) == 0 {
+            return;
+        }
+
+        if self.envelope_counter > 0 {
+            self.envelope_counter -= 1;
+            return;
+        }
+        self.envelope_counter = self.reg_ev0 & 0x07;
+
+        let grow = (self.reg_ev0 & 0x08) != 0;
+        if grow && self.envelope_level < MAX_ENVELOPE {
+            self.envelope_level += 1;
+        } else if !grow && self.envelope_level > 0 {
+            self.envelope_level -= 1;
+        } else if (self.reg_ev1 & 0x02) != 0 {
+            self.envelope_level = self.reg_ev0 >> 4;
+        }
+    }
+
+    // Applies channel 5's sweep or modulation, clocked every 1.04ms tick
+    pub fn sweep_modulation_tick(&mut self, modulation_table: &[i8]) {
+        if self.kind != ChannelKind::SweepModulation || !self.enabled || (self.reg_ev1 & 0x40) == 0 {
+            return;
+        }
+
+        let interval = self.sweep_modulation_interval();
+        if interval == 0 {
+            return;
+        }
+        if self.sweep_modulation_counter > 1 {
+            self.sweep_modulation_counter -= 1;
+            return;
+        }
+        self.sweep_modulation_counter = interval;
+
+        if (self.reg_ev1 & 0x10) != 0 {
+            // Modulation adds each entry of the table in turn to the written frequency, stopping on the last
+            // entry unless repeat is set
+            let offset = modulation_table[self.modulation_index] as i32;
+            self.frequency = ((self.reg_frequency as i32 + offset) as u16) & MAX_FREQUENCY;
+            if self.modulation_index + 1 < modulation_table.len() {
+                self.modulation_index += 1;
+            } else if (self.reg_ev1 & 0x20) != 0 {
+                self.modulation_index = 0;
+            }
+        } else {
+            let delta = self.frequency >> (self.reg_swp & 0x07);
+            let frequency = if (self.reg_swp & 0x08) != 0 {
+                self.frequency + delta
+            } else {
+                self.frequency.saturating_sub(delta)
+            };
+
+            // Sweeping past the top of the frequency range silences the channel
+            if frequency > MAX_FREQUENCY {
+                self.enabled = false;
+            } else {
+                self.frequency = frequency;
+            }
+        }
+    }
+
+    // The channel's current 6 bit sample, or None if it isn't producing any output
+    pub fn sample(&self, waves: &[[u8; NUM_SAMPLES]]) -> Option<u8> {
+        if !self.enabled {
+            return None;
+        }
+
+        match self.kind {
+            ChannelKind::Wave | ChannelKind::SweepModulation => waves.get(self.reg_ram as usize).map(|wave| wave[self.sample_index]),
+            ChannelKind::Noise => Some(if (self.lfsr & 0x01) != 0 { MAX_SAMPLE } else { 0 }),
+        }
+    }
+
+    pub fn envelope_level(&self) -> u8 {
+        self.envelope_level
+    }
+
+    // Number of 1.04ms ticks between sweep or modulation steps, S5SWP's clock bit slows the ticks to 8.32ms
+    fn sweep_modulation_interval(&self) -> usize {
+        let interval = ((self.reg_swp >> 4) & 0x07) as usize;
+        if (self.reg_swp & 0x80) != 0 { interval * 8 } else { interval }
+    }
+
+    fn period(&self) -> usize {
+        let divider = match self.kind {
+            ChannelKind::Wave | ChannelKind::SweepModulation => WAVE_CLOCK_DIVIDER,
+            ChannelKind::Noise => NOISE_CLOCK_DIVIDER,
+        };
+        (2048 - self.frequency as usize) * divider
+    }
+
+    fn step(&mut self) {
+        match self.kind {
+            ChannelKind::Wave | ChannelKind::SweepModulation => self.sample_index = (self.sample_index + 1) % NUM_SAMPLES,
+            ChannelKind::Noise => {
+                let tap = NOISE_TAPS[((self.reg_ev1 >> 4) & 0x07) as usize];
+                let feedback = ((self.lfsr >> 7) ^ (self.lfsr >> tap)) & 0x01;
+                self.lfsr = ((self.lfsr << 1) | feedback) & NOISE_LFSR_RESET;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(kind: ChannelKind, frequency: u16, ev0: u8, ev1: u8, swp: u8) -> Channel {
+        let mut channel = Channel::new(kind);
+        channel.write_frequency_low(frequency as u8);
+        channel.write_frequency_high((frequency >> 8) as u8);
+        channel.write_ev0(ev0);
+        channel.reg_ev1 = ev1;
+        channel.reg_swp = swp;
+        channel.write_int(0x80);
+        channel
+    }
+
+    #[test]
+    fn envelope_decay() {
+        // Starting at 10 and stepping down every second tick
+        let mut channel = start(ChannelKind::Wave, 0, 0xa1, 0x01, 0);
+        for _ in 0..6 {
+            channel.envelope_tick();
+        }
+        assert_eq!(channel.envelope_level(), 7);
+
+        // Holding at silence without repeat
+        for _ in 0..30 {
+            channel.envelope_tick();
+        }
+        assert_eq!(channel.envelope_level(), 0);
+
+        // Restarting from the initial level with repeat
+        channel.reg_ev1 = 0x03;
+        channel.envelope_tick();
+        channel.envelope_tick();
+        assert_eq!(channel.envelope_level(), 10);
+    }
+
+    #[test]
+    fn envelope_grow() {
+        let mut channel = start(ChannelKind::Wave, 0, 0x38, 0x01, 0);
+        for _ in 0..4 {
+            channel.envelope_tick();
+        }
+        assert_eq!(channel.envelope_level(), 7);
+
+        for _ in 0..20 {
+            channel.envelope_tick();
+        }
+        assert_eq!(channel.envelope_level(), MAX_ENVELOPE);
+    }
+
+    #[test]
+    fn sweep_down() {
+        let mut channel = start(ChannelKind::SweepModulation, 0x400, 0, 0x40, 0x11);
+        channel.sweep_modulation_tick(&[]);
+        assert_eq!(channel.frequency, 0x200);
+        channel.sweep_modulation_tick(&[]);
+        assert_eq!(channel.frequency, 0x100);
+        assert_eq!(channel.reg_frequency, 0x400);
+    }
+
+    #[test]
+    fn sweep_overflow_stops_channel() {
+        let mut channel = start(ChannelKind::SweepModulation, 0x400, 0, 0x40, 0x19);
+        channel.sweep_modulation_tick(&[]);
+        assert_eq!(channel.frequency, 0x600);
+        assert!(channel.is_enabled());
+
+        channel.sweep_modulation_tick(&[]);
+        assert!(!channel.is_enabled());
+        assert_eq!(channel.frequency, 0x600);
+    }
+
+    #[test]
+    fn modulation_steps() {
+        let table = [1, -2, 3];
+
+        // Every second tick, holding the last entry without repeat
+        let mut channel = start(ChannelKind::SweepModulation, 0x100, 0, 0x50, 0x20);
+        let mut frequencies = Vec::new();
+        for _ in 0..8 {
+            channel.sweep_modulation_tick(&table);
+            frequencies.push(channel.frequency);
+        }
+        assert_eq!(frequencies, [0x100, 0x101, 0x101, 0x0fe, 0x0fe, 0x103, 0x103, 0x103]);
+
+        // Going back to the first entry with repeat
+        let mut channel = start(ChannelKind::SweepModulation, 0x100, 0, 0x70, 0x10);
+        for _ in 0..4 {
+            channel.sweep_modulation_tick(&table);
+        }
+        assert_eq!(channel.frequency, 0x101);
+    }
+}
@@ -0,0 +1,240 @@
+mod channel;
+
+use std::io;
+
+use super::audio::{AudioOutput, CYCLES_PER_SAMPLE};
+use super::bus::BusDevice;
+use self::channel::{Channel, ChannelKind, NUM_SAMPLES, MAX_SAMPLE};
+
+// The VSU only decodes the bottom 11 bits of the address, registers are 8 bits wide and sit on word boundaries
+const ADDR_MASK: u32 = 0x000007ff;
+
+const WAVE_RAM_START: u32 = 0x00000000;
+const WAVE_RAM_END: u32 = 0x0000027f;
+const WAVE_RAM_SIZE: u32 = 0x00000080;
+const NUM_WAVES: usize = 5;
+
+const MODULATION_TABLE_START: u32 = 0x00000280;
+const MODULATION_TABLE_END: u32 = 0x000002ff;
+const MODULATION_TABLE_SIZE: usize = 32;
+
+const CHANNELS_START: u32 = 0x00000400;
+const CHANNELS_END: u32 = 0x0000057f;
+const CHANNEL_SIZE: u32 = 0x00000040;
+const NUM_CHANNELS: usize = 6;
+const SWEEP_MODULATION_CHANNEL: usize = 4;
+const NOISE_CHANNEL: usize = 5;
+
+const REG_INT: u32 = 0x00;
+const REG_LRV: u32 = 0x04;
+const REG_FQL: u32 = 0x08;
+const REG_FQH: u32 = 0x0c;
+const REG_EV0: u32 = 0x10;
+const REG_EV1: u32 = 0x14;
+const REG_RAM: u32 = 0x18;
+const REG_SWP: u32 = 0x1c;
+
+const REG_SSTOP: u32 = 0x00000580;
+
+// Interval counters tick every 3.84ms, envelopes every 15.36ms and sweep and modulation every 1.04ms
+const INTERVAL_CYCLES: usize = 76800;
+const ENVELOPE_CYCLES: usize = 307200;
+const SWEEP_MODULATION_CYCLES: usize = 20800;
+
+// The loudest a mix can get, every channel playing its top sample at full envelope and volume
+const MAX_AMPLITUDE: u32 = NUM_CHANNELS as u32 * MAX_SAMPLE as u32 * (((15 * 15) >> 3) + 1);
+
+pub struct Vsu {
+    waves: [[u8; NUM_SAMPLES]; NUM_WAVES],
+    modulation_table: [i8; MODULATION_TABLE_SIZE],
+    channels: Vec<Channel>,
+
+    interval_clock: usize,
+    envelope_clock: usize,
+    sweep_modulation_clock: usize,
+    sample_clock: usize,
+
+    audio: AudioOutput,
+}
+
+impl Vsu {
+    pub fn new() -> Self {
+        let channels = (0..NUM_CHANNELS).map(|index| match index {
+            SWEEP_MODULATION_CHANNEL => Channel::new(ChannelKind::SweepModulation),
+            NOISE_CHANNEL => Channel::new(ChannelKind::Noise),
+            _ => Channel::new(ChannelKind::Wave),
+        }).collect();
+
+        Vsu {
+            waves: [[0; NUM_SAMPLES]; NUM_WAVES],
+            modulation_table: [0; MODULATION_TABLE_SIZE],
+            channels,
+
+            interval_clock: 0,
+            envelope_clock: 0,
+            sweep_modulation_clock: 0,
+            sample_clock: 0,
+
+            audio: AudioOutput::new(),
+        }
+    }
+
+    pub fn cycles(&mut self, cycles: usize) {
+        let mut cycles_left = cycles;
+        while cycles_left > 0 {
+            let step = cycles_left
+                .min(INTERVAL_CYCLES - self.interval_clock)
+                .min(ENVELOPE_CYCLES - self.envelope_clock)
+                .min(SWEEP_MODULATION_CYCLES - self.sweep_modulation_clock)
+                .min(CYCLES_PER_SAMPLE - self.sample_clock);
+            for channel in self.channels.iter_mut() {
+                channel.cycles(step);
+            }
+
+            self.interval_clock += step;
+            if self.interval_clock == INTERVAL_CYCLES {
+                self.interval_clock = 0;
+                for channel in self.channels.iter_mut() {
+                    channel.interval_tick();
+                }
+            }
+
+            self.envelope_clock += step;
+            if self.envelope_clock == ENVELOPE_CYCLES {
+                self.envelope_clock = 0;
+                for channel in self.channels.iter_mut() {
+                    channel.envelope_tick();
+                }
+            }
+
+            self.sweep_modulation_clock += step;
+            if self.sweep_modulation_clock == SWEEP_MODULATION_CYCLES {
+                self.sweep_modulation_clock = 0;
+                self.channels[SWEEP_MODULATION_CHANNEL].sweep_modulation_tick(&self.modulation_table);
+            }
+
+            self.sample_clock += step;
+            if self.sample_clock == CYCLES_PER_SAMPLE {
+                self.sample_clock = 0;
+                let (left, right) = self.output();
+                self.audio.push([to_sample(left), to_sample(right)]);
+            }
+
+            cycles_left -= step;
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.audio.sample_rate()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) -> io::Result<()> {
+        self.audio.set_sample_rate(sample_rate)
+    }
+
+    pub fn samples_available(&self) -> usize {
+        self.audio.samples_available()
+    }
+
+    pub fn read_samples(&mut self, out: &mut [i16]) -> usize {
+        self.audio.read_samples(out)
+    }
+
+    // The current mix of all the channels as unsigned left and right amplitudes
+    fn output(&self) -> (u32, u32) {
+        let mut left = 0;
+        let mut right = 0;
+        for channel in self.channels.iter() {
+            if let Some(sample) = channel.sample(&self.waves) {
+                let envelope = channel.envelope_level() as u32;
+                left += sample as u32 * amplitude(envelope, channel.reg_left_volume as u32);
+                right += sample as u32 * amplitude(envelope, channel.reg_right_volume as u32);
+            }
+        }
+        (left, right)
+    }
+
+    fn write_channel_register(&mut self, index: usize, reg: u32, val: u8) {
+        let channel = &mut self.channels[index];
+        match reg {
+            REG_INT => channel.write_int(val),
+            REG_LRV => {
+                channel.reg_left_volume = val >> 4;
+                channel.reg_right_volume = val & 0x0f;
+            }
+            REG_FQL => channel.write_frequency_low(val),
+            REG_FQH => channel.write_frequency_high(val),
+            REG_EV0 => channel.write_ev0(val),
+            REG_EV1 => channel.reg_ev1 = val,
+            REG_RAM if index != NOISE_CHANNEL => channel.reg_ram = val & 0x07,
+            REG_SWP if index == SWEEP_MODULATION_CHANNEL => channel.reg_swp = val,
+            _ => println!("WARN: Writing to unknown VSU channel {} register [0x{:02x}] = 0x{:02x}", index + 1, reg, val),
+        }
+    }
+}
+
+impl BusDevice for Vsu {
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        println!("WARN: Reading from write only VSU [0x{:08x}]", addr & ADDR_MASK);
+        0
+    }
+
+    fn read_halfword(&mut self, addr: u32) -> u16 {
+        println!("WARN: Reading from write only VSU [0x{:08x}]", addr & ADDR_MASK);
+        0
+    }
+
+    fn write_byte(&mut self, addr: u32, val: u8) {
+        let addr = addr & ADDR_MASK & 0xfffffffc;
+        match addr {
+            WAVE_RAM_START..=WAVE_RAM_END => {
+                // The waveforms can't be changed while any channel is playing
+                if self.channels.iter().any(|c| c.is_enabled()) {
+                    return;
+                }
+                let wave = (addr / WAVE_RAM_SIZE) as usize;
+                let index = ((addr % WAVE_RAM_SIZE) / 4) as usize;
+                self.waves[wave][index] = val & MAX_SAMPLE;
+            }
+            MODULATION_TABLE_START..=MODULATION_TABLE_END => {
+                let index = ((addr - MODULATION_TABLE_START) / 4) as usize;
+                self.modulation_table[index] = val as i8;
+            }
+            CHANNELS_START..=CHANNELS_END => {
+                let index = ((addr - CHANNELS_START) / CHANNEL_SIZE) as usize;
+                self.write_channel_register(index, addr % CHANNEL_SIZE, val);
+            }
+            REG_SSTOP => {
+                if (val & 0x01) != 0 {
+                    for channel in self.channels.iter_mut() {
+                        channel.stop();
+                    }
+                }
+            }
+            _ => println!("WARN: Writing to unknown VSU address [0x{:08x}] = 0x{:02x}", addr, val),
+        }
+    }
+
+    fn write_halfword(&mut self, addr: u32, val: u16) {
+        // Only the low byte of the data bus is connected
+        self.write_byte(addr, val as u8);
+    }
+
+    fn peek(&self, _addr: u32) -> u8 {
+        0
+    }
+}
+
+// Scales a channel's envelope by its stereo volume, any non-zero pair always produces some output
+fn amplitude(envelope: u32, volume: u32) -> u32 {
+    if envelope == 0 || volume == 0 {
+        0
+    } else {
+        ((envelope * volume) >> 3) + 1
+    }
+}
+
+// The mixed output is never negative, AudioOutput takes out the DC offset before handing it on
+fn to_sample(amplitude: u32) -> i16 {
+    (amplitude * i16::MAX as u32 / MAX_AMPLITUDE) as i16
+}
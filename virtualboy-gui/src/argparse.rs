@@ -1,7 +1,12 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use clap::{App, Arg};
 
+use virtualboy_core::virtualboy::RamInit;
+
 pub struct CmdLineCfg {
-    pub rom_path: String
+    pub rom_path: String,
+    pub wram_init: Option<RamInit>,
 }
 
 pub fn parse_args() -> CmdLineCfg {
@@ -13,12 +18,37 @@ pub fn parse_args() -> CmdLineCfg {
             .help("The path to the ROM to load")
             .required(true)
             .index(1)
+        )
+        .arg(Arg::with_name("wram-init")
+            .help("Power on contents of WRAM: zero, random or a byte value such as 0xa5")
+            .long("wram-init")
+            .takes_value(true)
+            .validator(|val| parse_ram_init(&val).map(|_| ()))
         );
     let matches = app.get_matches();
 
     let rom_path = matches.value_of("ROM").unwrap();
+    let wram_init = matches.value_of("wram-init").map(|val| parse_ram_init(val).unwrap());
 
     CmdLineCfg{
         rom_path: rom_path.into(),
+        wram_init,
+    }
+}
+
+fn parse_ram_init(val: &str) -> Result<RamInit, String> {
+    match val {
+        "zero" => Ok(RamInit::Zero),
+        "random" => {
+            let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos();
+            Ok(RamInit::Random(seed))
+        }
+        _ => {
+            let byte = match val.strip_prefix("0x") {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => val.parse(),
+            };
+            byte.map(RamInit::Fill).map_err(|_| format!("Invalid RAM init value \"{}\"", val))
+        }
     }
-}
\ No newline at end of file
+}
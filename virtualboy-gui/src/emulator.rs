@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::time::SystemTime;
 use std::thread::{self, JoinHandle};
 use std::io::{stdin, stdout, Write};
@@ -7,7 +8,7 @@ use super::windows::debug::DebugWindow;
 use super::windows::main::MainWindow;
 
 use virtualboy_core::rom::Rom;
-use virtualboy_core::virtualboy::VirtualBoy;
+use virtualboy_core::virtualboy::{RamInit, VirtualBoy};
 use virtualboy_core::instruction::{self, Instruction};
 
 use super::command::Command;
@@ -67,6 +68,17 @@ impl Emulator {
         e
     }
 
+    pub fn attach_save_file<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        if let Err(e) = self.vb.attach_save_file(path) {
+            println!("Failed to load save file {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn init_wram(&mut self, init: RamInit) {
+        self.vb.init_wram(init);
+    }
+
     pub fn run(&mut self) {
         let mut last_loop_time = SystemTime::now();
         let mut nanos_to_cover = 0;
@@ -78,6 +90,7 @@ impl Emulator {
 
             match self.mode {
                 Mode::Running => {
+                    self.vb.set_buttons(self.main_window.buttons());
                     while nanos_to_cover > 0 {
                         self.cursor = self.vb.cpu.reg_pc();
                         let instr = self.disassemble_instruction();
@@ -109,24 +122,28 @@ impl Emulator {
 
             match command {
                 Ok(Command::ShowRegs) => {
-                    // println!("pc: 0x{:08x}", self.virtual_boy.cpu.reg_pc());
-                    // println!("gpr:");
-                    // for i in 0..32 {
-                    //     println!(" r{}: 0x{:08x}", i, self.virtual_boy.cpu.reg_gpr(i));
-                    // }
-                    // println!("psw: 0x{:08x}", self.virtual_boy.cpu.reg_psw());
-                    // println!("eipc: 0x{:08x}", self.virtual_boy.cpu.reg_eipc());
-                    // println!("eipsw: 0x{:08x}", self.virtual_boy.cpu.reg_eipsw());
-                    // println!("ecr: 0x{:08x}", self.virtual_boy.cpu.reg_ecr());
+                    println!("pc: 0x{:08x}", self.vb.cpu.reg_pc());
+                    println!("gpr:");
+                    for i in 0..32 {
+                        println!(" r{}: 0x{:08x}", i, self.vb.cpu.reg_gpr(i));
+                    }
+                    println!("psw: 0x{:08x}", self.vb.cpu.reg_psw());
+                    println!("eipc: 0x{:08x}", self.vb.cpu.reg_eipc());
+                    println!("eipsw: 0x{:08x}", self.vb.cpu.reg_eipsw());
+                    println!("fepc: 0x{:08x}", self.vb.cpu.reg_fepc());
+                    println!("fepsw: 0x{:08x}", self.vb.cpu.reg_fepsw());
+                    println!("ecr: 0x{:08x}", self.vb.cpu.reg_ecr());
+                    println!("chcw: 0x{:08x}", self.vb.cpu.reg_chcw());
+                    println!("adtre: 0x{:08x}", self.vb.cpu.reg_adtre());
                 }
                 Ok(Command::ShowCpuCache) => {
-                    // println!("CPU Instruction Cached enable: {}", self.virtual_boy.cpu.cache.is_enabled());
-                    // let (hits, misses) = self.virtual_boy.cpu.cache.stats();
-                    // let percent_hit = (hits as f64 / (hits + misses) as f64) * 100.0;
-                    // println!("Cache Hits: {}, Cache Misses: {} ({:.1}% hit rate)", hits, misses, percent_hit);
-                    // for i in 0..128 {
-                    //     println!("Entry {:3}: {}", i, self.virtual_boy.cpu.cache.entry(i));
-                    // }
+                    println!("CPU Instruction Cached enable: {}", self.vb.cpu.cache.is_enabled());
+                    let (hits, misses) = self.vb.cpu.cache.stats();
+                    let percent_hit = (hits as f64 / (hits + misses) as f64) * 100.0;
+                    println!("Cache Hits: {}, Cache Misses: {} ({:.1}% hit rate)", hits, misses, percent_hit);
+                    for i in 0..128 {
+                        println!("Entry {:3}: {}", i, self.vb.cpu.cache.entry(i));
+                    }
                 },
                 Ok(Command::Step(count)) => {
                     for _ in 0..count {
@@ -237,8 +254,8 @@ impl Emulator {
     }
 
     fn disassemble_instruction(&self) -> Instruction {
-        let a = self.vb.interconnect.read_halfword(self.cursor);
-        let b = self.vb.interconnect.read_halfword(self.cursor.wrapping_add(2));
+        let a = self.vb.interconnect.peek_halfword(self.cursor);
+        let b = self.vb.interconnect.peek_halfword(self.cursor.wrapping_add(2));
 
         instruction::from_halfwords(a, b)
     }
@@ -249,7 +266,7 @@ impl Emulator {
     }
 
     fn update_windows(&mut self) {
-        self.main_window.update();
+        self.main_window.update_with_vb(&self.vb);
         if let Some(ref mut dwind) = self.debug_window {
             dwind.update_with_vb(&self.vb);
 
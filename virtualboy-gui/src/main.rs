@@ -14,6 +14,7 @@ mod windows;
 
 use emulator::Emulator;
 use virtualboy_core::rom::Rom;
+use virtualboy_core::virtualboy::save_file_path;
 
 fn humanize(size: usize) -> String {
     if size < 1024 {
@@ -40,5 +41,9 @@ fn main() {
     println!(" game version: \"{}\"", rom.game_version());
 
     let mut emulator = Emulator::new(rom);
+    emulator.attach_save_file(save_file_path(&cmd_line_cfg.rom_path));
+    if let Some(wram_init) = cmd_line_cfg.wram_init {
+        emulator.init_wram(wram_init);
+    }
     emulator.run();
 }
@@ -1,13 +1,14 @@
-use minifb::{Menu, Scale, ScaleMode, Window, WindowOptions};
+use minifb::{Key, Menu, Scale, ScaleMode, Window, WindowOptions};
 
-const VB_WIDTH: usize = 384;
-const VB_HEIGHT: usize = 224;
+use virtualboy_core::virtualboy::{Buttons, Eye, VirtualBoy, FRAME_WIDTH, FRAME_HEIGHT};
+
+const VB_WIDTH: usize = FRAME_WIDTH;
+const VB_HEIGHT: usize = FRAME_HEIGHT;
 
 pub const MENU_FILE_OPEN: usize = 1;
 pub const MENU_FILE_EXIT: usize = 2;
 pub const MENU_VIEW_DEBUG: usize = 3;
 
-#[allow(dead_code)]
 pub struct MainWindow {
     window: Window,
 
@@ -45,12 +46,37 @@ impl MainWindow {
         }
     }
 
-    pub fn update(&mut self) {
+    pub fn update_with_vb(&mut self, vb: &VirtualBoy) {
         if !self.window.is_open() {
             return;
         }
 
-        self.window.update();
+        // Only the left eye is shown, in the red of the Virtual Boy's LEDs
+        for (pixel, &intensity) in self.buffer.iter_mut().zip(vb.frame(Eye::Left).iter()) {
+            *pixel = (intensity as u32) << 16;
+        }
+
+        self.window.update_with_buffer(&self.buffer, self.width, self.height).unwrap();
+    }
+
+    pub fn buttons(&self) -> Buttons {
+        let key = |k| self.window.is_key_down(k);
+        Buttons {
+            a: key(Key::M),
+            b: key(Key::N),
+            l: key(Key::Q),
+            r: key(Key::E),
+            start: key(Key::Enter),
+            select: key(Key::Space),
+            left_up: key(Key::W),
+            left_down: key(Key::S),
+            left_left: key(Key::A),
+            left_right: key(Key::D),
+            right_up: key(Key::I),
+            right_down: key(Key::K),
+            right_left: key(Key::J),
+            right_right: key(Key::L),
+        }
     }
 
     pub fn is_open(&self) -> bool {